@@ -0,0 +1,28 @@
+//! Serial vs parallel throughput of batch detection, run with `--features rayon`
+
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use freq_det::{FreqDetector, WindowFunction};
+
+fn batch(c: &mut Criterion) {
+    let sample_count = 4096;
+    let windows = 256;
+    let samples = (0..sample_count * windows)
+        .map(|i| (i as f32 / 44100.0 * 440.0 * std::f32::consts::TAU).sin())
+        .collect::<Vec<_>>();
+    let detector = FreqDetector::with_window(44100, sample_count, WindowFunction::Hann).unwrap();
+
+    let mut group = c.benchmark_group("batch");
+    group.throughput(Throughput::Elements(windows as u64));
+    group.bench_function("serial", |b| {
+        b.iter(|| detector.detect_batch(black_box(&samples)))
+    });
+    group.bench_function("parallel", |b| {
+        b.iter(|| detector.detect_batch_par(black_box(&samples)))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, batch);
+criterion_main!(benches);
@@ -0,0 +1,109 @@
+//! Compares [FreqDetector::detect] with the buffer-reusing [FreqDetector::detect_mut]
+//! and the real-input FFT used by the detector with a complex one of the same length.
+//!
+//! Also prints how many heap allocations a single call makes.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    hint::black_box,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use freq_det::{FreqDetector, WindowFunction};
+use realfft::RealFftPlanner;
+use rustfft::{num_complex::Complex, FftPlanner};
+
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+fn allocations_of(f: impl FnOnce()) -> usize {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    f();
+    ALLOCATIONS.load(Ordering::Relaxed) - before
+}
+
+fn sinusoid(sample_count: usize) -> Vec<f32> {
+    (0..sample_count)
+        .map(|i| (i as f32 / 44100.0 * 440.0 * std::f32::consts::TAU).sin())
+        .collect()
+}
+
+fn detect(c: &mut Criterion) {
+    let sample_count = 4096;
+    let samples = sinusoid(sample_count);
+    let mut detector =
+        FreqDetector::with_window(44100, sample_count, WindowFunction::Hann).unwrap();
+
+    // warm up, so that lazy initialization is not counted
+    detector.detect_mut(&samples).unwrap();
+    eprintln!(
+        "allocations per call: detect {}, detect_mut {}",
+        allocations_of(|| {
+            detector.detect(&samples).unwrap();
+        }),
+        allocations_of(|| {
+            detector.detect_mut(&samples).unwrap();
+        }),
+    );
+
+    let mut group = c.benchmark_group("detect");
+    group.bench_function("detect", |b| {
+        b.iter(|| detector.detect(black_box(&samples)).unwrap())
+    });
+    group.bench_function("detect_mut", |b| {
+        b.iter(|| detector.detect_mut(black_box(&samples)).unwrap())
+    });
+    group.finish();
+}
+
+fn fft(c: &mut Criterion) {
+    let sample_count = 8192;
+    let samples = sinusoid(sample_count);
+
+    let complex_fft = FftPlanner::new().plan_fft_forward(sample_count);
+    let mut complex_buf = vec![Complex::default(); sample_count];
+    let mut complex_scratch = vec![Complex::default(); complex_fft.get_inplace_scratch_len()];
+
+    let real_fft = RealFftPlanner::new().plan_fft_forward(sample_count);
+    let mut real_input = real_fft.make_input_vec();
+    let mut real_output = real_fft.make_output_vec();
+    let mut real_scratch = real_fft.make_scratch_vec();
+
+    let mut group = c.benchmark_group("fft");
+    group.bench_function("complex", |b| {
+        b.iter(|| {
+            for (c, s) in complex_buf.iter_mut().zip(&samples) {
+                *c = Complex { re: *s, im: 0.0 };
+            }
+            complex_fft.process_with_scratch(&mut complex_buf, &mut complex_scratch);
+        })
+    });
+    group.bench_function("real", |b| {
+        b.iter(|| {
+            real_input.copy_from_slice(&samples);
+            real_fft
+                .process_with_scratch(&mut real_input, &mut real_output, &mut real_scratch)
+                .unwrap();
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, detect, fft);
+criterion_main!(benches);
@@ -0,0 +1,77 @@
+/*
+ * C interface of freq-det, enabled with the `ffi` feature.
+ *
+ * Link the cdylib built by
+ *     cargo build --release --features ffi
+ */
+
+#ifndef FREQ_DET_H
+#define FREQ_DET_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+typedef struct FreqDetector FreqDetector;
+
+/**
+ * Success, the error codes and their values never change between versions
+ */
+#define FREQ_DET_OK 0
+
+#define FREQ_DET_NULL_POINTER -1
+
+#define FREQ_DET_SAMPLE_COUNT_MISMATCH -2
+
+#define FREQ_DET_NANS_FOUND -3
+
+#define FREQ_DET_NON_FINITE -4
+
+#define FREQ_DET_EMPTY_SPECTRUM -5
+
+#define FREQ_DET_NO_SUCH_CHANNEL -6
+
+#define FREQ_DET_NO_CHANNELS -7
+
+#define FREQ_DET_INCOMPLETE_FRAME -8
+
+#define FREQ_DET_PANIC -100
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Creates an `f32` detector, see [FreqDetector::new].
+ *
+ * Returns null if the parameters are invalid or creating it panicked.
+ * The detector has to be released with [freq_det_free].
+ */
+FreqDetector *freq_det_new(size_t sample_rate, size_t sample_count);
+
+/**
+ * Detects the frequency of `len` samples into `freq`, see [FreqDetector::detect_mut].
+ *
+ * Returns [FREQ_DET_OK] on success, or another `FREQ_DET_*` code, in which case
+ * `freq` is left alone. Panics are caught and reported as [FREQ_DET_PANIC].
+ *
+ * # Safety
+ *
+ * `detector` has to come from [freq_det_new] and not be used from another thread
+ * at the same time, `samples` has to point to `len` floats and `freq` to a float.
+ */
+int32_t freq_det_detect(FreqDetector *detector, const float *samples, size_t len, float *freq);
+
+/**
+ * Releases a detector created by [freq_det_new], does nothing for null.
+ *
+ * # Safety
+ *
+ * `detector` must not be used afterwards.
+ */
+void freq_det_free(FreqDetector *detector);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* FREQ_DET_H */
@@ -0,0 +1,400 @@
+use std::sync::{Arc, OnceLock};
+
+use realfft::{RealFftPlanner, RealToComplex};
+use rustfft::{num_traits::Float, FftNum};
+
+use crate::{
+    float, padded_len, window::PeakOffsetTable, DetectorCreateError, FreqDetector, Interpolation,
+    OutputUnit, PeakPicker, Scratch, StrongestPicker, WindowFunction,
+};
+
+/// Configures a [FreqDetector] before creating it
+///
+/// ```
+/// use freq_det::{FreqDetectorBuilder, Interpolation, WindowFunction};
+///
+/// let freq_detector = FreqDetectorBuilder::<f32>::new(44100, 4096)
+///     .window(WindowFunction::Hann)
+///     .interpolation(Interpolation::Parabolic)
+///     .freq_range(60.0, 1500.0)
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Clone, Debug)]
+pub struct FreqDetectorBuilder<T = f32> {
+    config: FreqDetectorConfig<T>,
+    /// Not part of the config, as it can be any type
+    peak_picker: Option<Arc<dyn PeakPicker<T>>>,
+}
+
+/// Plain parameters of a [FreqDetector], everything needed to create it again.
+///
+/// With the `serde` feature it can be stored with the rest of the settings
+/// of an application and turned back into a detector with [FreqDetector::from_config].
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FreqDetectorConfig<T = f32> {
+    pub sample_rate: usize,
+    pub sample_count: usize,
+    /// See [FreqDetector::with_window]
+    pub window: WindowFunction,
+    /// See [FreqDetector::set_interpolation]
+    pub interpolation: Interpolation,
+    /// See [FreqDetector::set_output_unit]
+    pub output_unit: OutputUnit,
+    /// See [FreqDetector::set_freq_range]
+    pub freq_range: Option<(T, T)>,
+    /// See [FreqDetector::set_remove_dc]
+    pub remove_dc: bool,
+    /// See [FreqDetector::set_pre_emphasis]
+    pub pre_emphasis: Option<T>,
+    /// See [FreqDetectorBuilder::zero_pad_to]
+    pub zero_pad_to: Option<usize>,
+    /// See [FreqDetector::set_notch]
+    pub notch: Option<T>,
+    /// See [FreqDetector::set_noise_floor_width]
+    pub noise_floor_width: Option<usize>,
+    /// See [FreqDetector::set_silence_threshold]
+    pub silence_threshold: T,
+    /// See [FreqDetector::set_min_snr_db]
+    pub min_snr_db: Option<T>,
+}
+
+impl<T: FftNum + Float> FreqDetectorConfig<T> {
+    /// Defaults for everything but `sample_rate` and `sample_count`,
+    /// the same as in [FreqDetector::new]
+    pub fn new(sample_rate: usize, sample_count: usize) -> Self {
+        Self {
+            sample_rate,
+            sample_count,
+            window: WindowFunction::Rectangular,
+            interpolation: Interpolation::default(),
+            output_unit: OutputUnit::default(),
+            freq_range: None,
+            remove_dc: true,
+            pre_emphasis: None,
+            zero_pad_to: None,
+            notch: None,
+            noise_floor_width: None,
+            silence_threshold: float(0.0001),
+            min_snr_db: None,
+        }
+    }
+}
+
+impl<T> From<FreqDetectorConfig<T>> for FreqDetectorBuilder<T> {
+    fn from(config: FreqDetectorConfig<T>) -> Self {
+        Self {
+            config,
+            peak_picker: None,
+        }
+    }
+}
+
+impl<T: FftNum + Float> FreqDetectorBuilder<T> {
+    /// See [FreqDetector::new] for choosing `sample_rate` and `sample_count`
+    pub fn new(sample_rate: usize, sample_count: usize) -> Self {
+        FreqDetectorConfig::new(sample_rate, sample_count).into()
+    }
+
+    /// See [FreqDetector::with_window]
+    pub fn window(mut self, window: WindowFunction) -> Self {
+        self.config.window = window;
+        self
+    }
+
+    /// See [FreqDetector::set_interpolation]
+    pub fn interpolation(mut self, interpolation: Interpolation) -> Self {
+        self.config.interpolation = interpolation;
+        self
+    }
+
+    /// See [FreqDetector::set_freq_range]
+    pub fn freq_range(mut self, min_freq: T, max_freq: T) -> Self {
+        self.config.freq_range = Some((min_freq, max_freq));
+        self
+    }
+
+    /// See [FreqDetector::set_output_unit]
+    pub fn output_unit(mut self, output_unit: OutputUnit) -> Self {
+        self.config.output_unit = output_unit;
+        self
+    }
+
+    /// See [FreqDetector::set_remove_dc]
+    pub fn remove_dc(mut self, remove_dc: bool) -> Self {
+        self.config.remove_dc = remove_dc;
+        self
+    }
+
+    /// See [FreqDetector::set_pre_emphasis]
+    pub fn pre_emphasis(mut self, coefficient: T) -> Self {
+        self.config.pre_emphasis = Some(coefficient);
+        self
+    }
+
+    /// Pads the samples with zeros up to `fft_len` before the FFT, no padding by default.
+    ///
+    /// Buckets get narrower, so the spectrum is sampled more densely and peaks are refined
+    /// over a finer grid. It does not improve the true resolving power though: tones closer
+    /// than `sample_rate / sample_count` still merge into a single peak, since the padding
+    /// carries no information. Has no effect if `fft_len` is not above the sample count.
+    pub fn zero_pad_to(mut self, fft_len: usize) -> Self {
+        self.config.zero_pad_to = Some(fft_len);
+        self
+    }
+
+    /// See [FreqDetector::set_notch]
+    pub fn notch(mut self, freq: T) -> Self {
+        self.config.notch = Some(freq);
+        self
+    }
+
+    /// See [FreqDetector::set_noise_floor_width]
+    pub fn noise_floor_width(mut self, width: usize) -> Self {
+        self.config.noise_floor_width = Some(width);
+        self
+    }
+
+    /// See [FreqDetector::set_silence_threshold]
+    pub fn silence_threshold(mut self, threshold: T) -> Self {
+        self.config.silence_threshold = threshold;
+        self
+    }
+
+    /// See [FreqDetector::set_min_snr_db]
+    pub fn min_snr_db(mut self, db: T) -> Self {
+        self.config.min_snr_db = Some(db);
+        self
+    }
+
+    /// See [FreqDetector::set_peak_picker]
+    pub fn peak_picker(mut self, peak_picker: impl PeakPicker<T> + 'static) -> Self {
+        self.peak_picker = Some(Arc::new(peak_picker));
+        self
+    }
+
+    /// # Errors
+    /// - if sample rate is 0
+    /// - if fewer than 4 samples are passed
+    /// - if the frequency range is invalid, see [FreqDetector::set_freq_range]
+    pub fn build(self) -> Result<FreqDetector<T>, DetectorCreateError> {
+        self.check()?;
+        let mut planner = RealFftPlanner::new();
+        let fft = planner.plan_fft_forward(padded_len(
+            self.config.sample_count,
+            self.config.zero_pad_to,
+        ));
+        self.assemble(planner, fft)
+    }
+
+    /// Same as [Self::build], but uses an FFT planned in advance,
+    /// e.g. by a [RealFftPlanner] shared with other DSP code.
+    ///
+    /// # Errors
+    /// - same as [Self::build]
+    /// - if the length of `fft` is not the sample count, or the length set by
+    ///   [Self::zero_pad_to]
+    pub fn build_with_fft(
+        self,
+        fft: Arc<dyn RealToComplex<T>>,
+    ) -> Result<FreqDetector<T>, DetectorCreateError> {
+        self.check()?;
+        if fft.len() != padded_len(self.config.sample_count, self.config.zero_pad_to) {
+            return Err(DetectorCreateError::FftLengthMismatch);
+        }
+        self.assemble(RealFftPlanner::new(), fft)
+    }
+
+    fn check(&self) -> Result<(), DetectorCreateError> {
+        if self.config.sample_rate < 1 {
+            return Err(DetectorCreateError::SampleRateTooLow);
+        }
+        if self.config.sample_count < 4 {
+            return Err(DetectorCreateError::TooFewSamples);
+        }
+        Ok(())
+    }
+
+    fn assemble(
+        self,
+        planner: RealFftPlanner<T>,
+        fft: Arc<dyn RealToComplex<T>>,
+    ) -> Result<FreqDetector<T>, DetectorCreateError> {
+        let config = self.config;
+        let window = config.window.coefficients(config.sample_count);
+        let fft_len = fft.len();
+        let mut detector = FreqDetector {
+            scratch: Scratch::new(fft.as_ref()),
+            planner,
+            fft,
+            complex_fft: OnceLock::new(),
+            inverse_fft: OnceLock::new(),
+
+            sample_count: config.sample_count,
+            sample_rate: config.sample_rate,
+            window_function: config.window,
+            peak_offsets: PeakOffsetTable::new(&window, fft_len),
+            window: window.into_iter().map(float).collect(),
+            min_peak_separation: config
+                .window
+                .main_lobe_half_width(config.sample_count, fft_len),
+            interpolation: config.interpolation,
+            output_unit: config.output_unit,
+            freq_range: None,
+            remove_dc: config.remove_dc,
+            pre_emphasis: config.pre_emphasis,
+            zero_pad_to: config.zero_pad_to,
+            notch: None,
+            noise_floor_width: config.noise_floor_width,
+            silence_threshold: config.silence_threshold,
+            min_snr_db: config.min_snr_db,
+            peak_picker: self
+                .peak_picker
+                .unwrap_or_else(|| Arc::new(StrongestPicker)),
+        };
+        if let Some((min_freq, max_freq)) = config.freq_range {
+            detector.set_freq_range(min_freq, max_freq)?;
+        }
+        detector.set_notch(config.notch)?;
+        Ok(detector)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{DetectorCreateError, FreqDetector, Interpolation, WindowFunction};
+
+    use super::{FreqDetectorBuilder, FreqDetectorConfig};
+
+    #[test]
+    fn builder_applies_options() {
+        use std::f32::consts::TAU;
+        let sample_count = 4096;
+        let samples = (0..sample_count)
+            .map(|i| {
+                2.0 * (i as f32 / 44100.0 * 50.0 * TAU).sin()
+                    + 0.3 * (i as f32 / 44100.0 * 443.0 * TAU).sin()
+            })
+            .collect::<Vec<f32>>();
+
+        let built = FreqDetectorBuilder::new(44100, sample_count)
+            .window(WindowFunction::Hann)
+            .interpolation(Interpolation::Parabolic)
+            .freq_range(100.0, 1000.0)
+            .build()
+            .unwrap();
+        let mut configured =
+            FreqDetector::with_window(44100, sample_count, WindowFunction::Hann).unwrap();
+        configured.set_interpolation(Interpolation::Parabolic);
+        configured.set_freq_range(100.0, 1000.0).unwrap();
+
+        let detected = built.detect(&samples).unwrap();
+        assert!((detected - 443.0).abs() < 0.5, "detected {detected}");
+        assert_eq!(detected, configured.detect(&samples).unwrap());
+
+        assert!(matches!(
+            FreqDetectorBuilder::<f32>::new(44100, 4096)
+                .freq_range(1000.0, 100.0)
+                .build(),
+            Err(DetectorCreateError::EmptyFreqRange)
+        ));
+        assert!(matches!(
+            FreqDetectorBuilder::<f32>::new(0, 4096).build(),
+            Err(DetectorCreateError::SampleRateTooLow)
+        ));
+    }
+
+    #[test]
+    fn builds_with_shared_fft() {
+        use realfft::RealFftPlanner;
+
+        let mut planner = RealFftPlanner::<f32>::new();
+        let fft = planner.plan_fft_forward(1024);
+        let freq_detector = FreqDetector::from_fft(fft.clone(), 44100).unwrap();
+        assert_eq!(freq_detector.sample_count(), 1024);
+        let samples = (0..1024)
+            .map(|i| (i as f32 / 44100.0 * 1000.0 * std::f32::consts::TAU).sin())
+            .collect::<Vec<_>>();
+        assert!((freq_detector.detect(&samples).unwrap() - 1000.0).abs() < 2.0);
+
+        assert!(matches!(
+            FreqDetectorBuilder::new(44100, 2048).build_with_fft(fft),
+            Err(DetectorCreateError::FftLengthMismatch)
+        ));
+    }
+
+    #[test]
+    fn zero_padding_refines_the_grid() {
+        use std::f32::consts::TAU;
+        let sample_count = 1024;
+        let freq = 1234.5;
+        let samples = (0..sample_count)
+            .map(|i| (i as f32 / 44100.0 * freq * TAU).sin())
+            .collect::<Vec<f32>>();
+        let mut padded = FreqDetectorBuilder::new(44100, sample_count)
+            .window(WindowFunction::Hann)
+            .zero_pad_to(8192)
+            .build()
+            .unwrap();
+        assert_eq!(padded.sample_count(), sample_count);
+
+        let spectrum = padded.spectrum(&samples).unwrap();
+        assert_eq!(spectrum.len(), 8192 / 2 + 1);
+        assert!((spectrum[1].0 - 44100.0 / 8192.0).abs() < 1e-3);
+        let (peak_freq, _) = spectrum
+            .iter()
+            .copied()
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .unwrap();
+        assert!((peak_freq - freq).abs() < 44100.0 / 8192.0);
+
+        for interpolation in [Interpolation::WeightedAverage, Interpolation::Parabolic] {
+            padded.set_interpolation(interpolation);
+            let detected = padded.detect(&samples).unwrap();
+            assert!(
+                (detected - freq).abs() < 0.5,
+                "{interpolation:?}: {detected}"
+            );
+        }
+    }
+
+    #[test]
+    fn config_round_trip() {
+        let freq_detector = FreqDetectorBuilder::<f32>::new(48000, 2048)
+            .window(WindowFunction::Blackman)
+            .interpolation(Interpolation::Parabolic)
+            .freq_range(80.0, 1000.0)
+            .pre_emphasis(0.97)
+            .build()
+            .unwrap();
+        let config = freq_detector.config();
+        assert_eq!(config.window, WindowFunction::Blackman);
+        assert_eq!(config.freq_range, Some((80.0, 1000.0)));
+
+        let rebuilt = FreqDetector::from_config(config.clone()).unwrap();
+        assert_eq!(rebuilt.config(), config);
+        assert!(matches!(
+            FreqDetector::from_config(FreqDetectorConfig::<f32>::new(48000, 2)),
+            Err(DetectorCreateError::TooFewSamples)
+        ));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn config_serializes() {
+        let config = FreqDetectorBuilder::<f32>::new(44100, 4096)
+            .window(WindowFunction::Hann)
+            .zero_pad_to(8192)
+            .build()
+            .unwrap()
+            .config();
+        let json = serde_json::to_string(&config).unwrap();
+        let loaded: FreqDetectorConfig = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded, config);
+        assert_eq!(
+            FreqDetector::from_config(loaded).unwrap().sample_count(),
+            4096
+        );
+    }
+}
@@ -0,0 +1,316 @@
+//! Frequency detection on the default microphone, behind the `cpal` feature
+
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc::{self, Receiver, SyncSender},
+        Arc,
+    },
+    thread,
+};
+
+use cpal::{
+    traits::{DeviceTrait, HostTrait, StreamTrait},
+    Device, FromSample, Sample, SampleFormat, SizedSample, Stream, StreamConfig,
+};
+use thiserror::Error;
+
+use crate::{DetectorCreateError, FreqDetector, StreamingDetector};
+
+/// Mono blocks that can wait for the worker thread, any more are dropped
+const BLOCK_COUNT: usize = 8;
+/// Frames every block is allocated for up front, longer callbacks fill several blocks
+const BLOCK_FRAMES: usize = 4096;
+
+/// Runs a [StreamingDetector] on the default input device, delivering the detections
+/// through a channel
+///
+/// See [MicStream] for how the samples get to the detector, and to get a callback
+/// for every detection of a custom detector instead.
+/// Capturing stops when the `MicDetector` is dropped.
+///
+/// ```no_run
+/// use freq_det::capture::MicDetector;
+///
+/// // detect twice per window of 4096 samples
+/// let mic = MicDetector::open(4096, 2048).unwrap();
+/// for freq in mic.detections() {
+///     println!("{freq}");
+/// }
+/// ```
+pub struct MicDetector {
+    stream: MicStream,
+    detections: Receiver<f32>,
+}
+
+impl MicDetector {
+    /// Detects with a [FreqDetector::new] for the sample rate of the device,
+    /// detections arrive in [Self::detections].
+    ///
+    /// # Errors
+    /// - same as [MicStream::open] and [FreqDetector::new]
+    pub fn open(sample_count: usize, hop_size: usize) -> Result<Self, CaptureError> {
+        let (sender, detections) = mpsc::channel();
+        let stream = MicStream::open(
+            |sample_rate| FreqDetector::new(sample_rate, sample_count),
+            hop_size,
+            move |freq| {
+                // the receiver is gone only while the stream is being dropped
+                let _ = sender.send(freq);
+            },
+        )?;
+        Ok(Self { stream, detections })
+    }
+
+    /// Sample rate of the input device, which is the one of the detector
+    pub fn sample_rate(&self) -> usize {
+        self.stream.sample_rate()
+    }
+
+    /// See [MicStream::overruns]
+    pub fn overruns(&self) -> usize {
+        self.stream.overruns()
+    }
+
+    /// Detected frequencies, `0.0` for silence. Iterating blocks until the next one.
+    pub fn detections(&self) -> &Receiver<f32> {
+        &self.detections
+    }
+}
+
+/// Runs a [StreamingDetector] on the default input device, calling back for every
+/// detection
+///
+/// The audio callback only mixes the channels down to mono into preallocated blocks and
+/// hands them over to a worker thread, which runs the detector and gives the blocks back,
+/// so the audio thread neither blocks nor allocates. While the worker falls behind and
+/// all blocks are waiting, the samples of further callbacks are dropped, and the
+/// detector starts over with the samples after the gap, see [Self::overruns].
+/// Capturing stops when the `MicStream` is dropped.
+///
+/// ```no_run
+/// use freq_det::{capture::MicStream, FreqDetector};
+///
+/// let mic = MicStream::open(
+///     |sample_rate| FreqDetector::builder(sample_rate, 4096).freq_range(60.0, 1500.0).build(),
+///     2048,
+///     |freq| println!("{freq}"),
+/// )
+/// .unwrap();
+/// # drop(mic);
+/// ```
+pub struct MicStream {
+    sample_rate: usize,
+    overruns: Arc<AtomicUsize>,
+    _stream: Stream,
+}
+
+impl MicStream {
+    /// Calls `on_detection` on the worker thread for every detection of the detector
+    /// made by `build`, e.g. with [FreqDetector::builder].
+    ///
+    /// `build` gets the sample rate of the device. Silence is reported as `0.0`,
+    /// as in [StreamingDetector::push].
+    ///
+    /// # Errors
+    /// - if there is no input device or it cannot be opened
+    /// - if `build` fails
+    /// - same as [StreamingDetector::new]
+    pub fn open(
+        build: impl FnOnce(usize) -> Result<FreqDetector, DetectorCreateError>,
+        hop_size: usize,
+        on_detection: impl FnMut(f32) + Send + 'static,
+    ) -> Result<Self, CaptureError> {
+        let device = cpal::default_host()
+            .default_input_device()
+            .ok_or(CaptureError::NoInputDevice)?;
+        let supported = device.default_input_config()?;
+        let sample_format = supported.sample_format();
+        let config = StreamConfig::from(supported);
+        let sample_rate = config.sample_rate.0 as usize;
+        let streaming = StreamingDetector::new(build(sample_rate)?, hop_size)?;
+
+        // filled blocks go to the worker, empty ones back to the callback, both channels
+        // hold all blocks so sending never blocks
+        let (filled, samples) = mpsc::sync_channel(BLOCK_COUNT);
+        let (recycled, empty) = mpsc::sync_channel(BLOCK_COUNT);
+        for _ in 0..BLOCK_COUNT {
+            let _ = recycled.send(Block {
+                samples: Vec::with_capacity(BLOCK_FRAMES),
+                after_overrun: false,
+            });
+        }
+        let blocks = (filled, empty);
+        let stream = match sample_format {
+            SampleFormat::F32 => build_stream::<f32>(&device, &config, blocks)?,
+            SampleFormat::I16 => build_stream::<i16>(&device, &config, blocks)?,
+            SampleFormat::U16 => build_stream::<u16>(&device, &config, blocks)?,
+            SampleFormat::I32 => build_stream::<i32>(&device, &config, blocks)?,
+            format => return Err(CaptureError::UnsupportedFormat(format)),
+        };
+        let overruns = Arc::new(AtomicUsize::new(0));
+        let counted = Arc::clone(&overruns);
+        // ends once the stream and with it the sender of filled blocks are dropped
+        thread::spawn(move || detect_blocks(samples, recycled, streaming, &counted, on_detection));
+        stream.play()?;
+        Ok(Self {
+            sample_rate,
+            overruns,
+            _stream: stream,
+        })
+    }
+
+    /// Sample rate of the input device, which is the one of the detector
+    pub fn sample_rate(&self) -> usize {
+        self.sample_rate
+    }
+
+    /// Times the worker thread fell so far behind that samples were dropped.
+    ///
+    /// After every overrun the detector is reset with [StreamingDetector::reset],
+    /// so no detection is made of a window spanning the gap.
+    pub fn overruns(&self) -> usize {
+        self.overruns.load(Ordering::Relaxed)
+    }
+}
+
+/// Mono samples of a callback, handed from the audio thread to the worker thread
+struct Block {
+    samples: Vec<f32>,
+    /// Samples were dropped between the previous block and this one
+    after_overrun: bool,
+}
+
+/// Runs the worker thread until the sender of filled blocks is dropped
+fn detect_blocks(
+    filled: Receiver<Block>,
+    recycled: SyncSender<Block>,
+    mut streaming: StreamingDetector,
+    overruns: &AtomicUsize,
+    mut on_detection: impl FnMut(f32),
+) {
+    for block in filled {
+        if block.after_overrun {
+            streaming.reset();
+            overruns.fetch_add(1, Ordering::Relaxed);
+        }
+        if let Some(freq) = streaming.push(&block.samples) {
+            on_detection(freq);
+        }
+        let _ = recycled.try_send(block);
+    }
+}
+
+fn build_stream<S>(
+    device: &Device,
+    config: &StreamConfig,
+    (filled, empty): (SyncSender<Block>, Receiver<Block>),
+) -> Result<Stream, CaptureError>
+where
+    S: SizedSample,
+    f32: FromSample<S>,
+{
+    let channels = (config.channels as usize).max(1);
+    let mut overrun = false;
+    Ok(device.build_input_stream(
+        config,
+        move |data: &[S], _| {
+            for frames in data.chunks(BLOCK_FRAMES * channels) {
+                // no empty block left while the worker falls behind
+                let Ok(mut block) = empty.try_recv() else {
+                    overrun = true;
+                    return;
+                };
+                block.samples.clear();
+                mix_down(frames, channels, &mut block.samples);
+                block.after_overrun = std::mem::take(&mut overrun);
+                let _ = filled.try_send(block);
+            }
+        },
+        |error| eprintln!("Error reading data from input: {error}"),
+        None,
+    )?)
+}
+
+/// Appends the average of the channels of every interleaved frame to `mono`
+fn mix_down<S>(data: &[S], channels: usize, mono: &mut Vec<f32>)
+where
+    S: Sample,
+    f32: FromSample<S>,
+{
+    mono.extend(
+        data.chunks_exact(channels.max(1)).map(|frame| {
+            frame.iter().map(|s| s.to_sample::<f32>()).sum::<f32>() / frame.len() as f32
+        }),
+    );
+}
+
+#[derive(Error, Debug)]
+pub enum CaptureError {
+    #[error("No input device")]
+    NoInputDevice,
+    #[error("Input device has no usable config: {0}")]
+    Config(#[from] cpal::DefaultStreamConfigError),
+    #[error("Input device uses unsupported sample format {0}")]
+    UnsupportedFormat(SampleFormat),
+    #[error("Could not open the input stream: {0}")]
+    Build(#[from] cpal::BuildStreamError),
+    #[error("Could not start the input stream: {0}")]
+    Play(#[from] cpal::PlayStreamError),
+    #[error(transparent)]
+    Create(#[from] DetectorCreateError),
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{atomic::AtomicUsize, mpsc};
+
+    use crate::{FreqDetector, StreamingDetector};
+
+    use super::{detect_blocks, mix_down, Block};
+
+    #[test]
+    fn restarts_detecting_after_an_overrun() {
+        use std::f32::consts::TAU;
+        let tone = (0..4096)
+            .map(|i| (i as f32 / 44100.0 * 440.0 * TAU).sin())
+            .collect::<Vec<f32>>();
+        let (filled, samples) = mpsc::sync_channel(4);
+        let (recycled, empty) = mpsc::sync_channel(4);
+        for (i, chunk) in tone.chunks(1024).enumerate() {
+            let block = Block {
+                samples: chunk.to_vec(),
+                after_overrun: i == 2,
+            };
+            filled.send(block).unwrap();
+        }
+        drop(filled);
+
+        let detector = FreqDetector::new(44100, 2048).unwrap();
+        let streaming = StreamingDetector::new(detector, 1024).unwrap();
+        let overruns = AtomicUsize::new(0);
+        let mut detections = vec![];
+        detect_blocks(samples, recycled, streaming, &overruns, |freq| {
+            detections.push(freq)
+        });
+        // without the reset, the windows ending in the last two blocks would span the gap
+        assert_eq!(detections.len(), 2, "{detections:?}");
+        for freq in detections {
+            assert!((freq - 440.0).abs() < 1.0, "{freq}");
+        }
+        assert_eq!(overruns.into_inner(), 1);
+        // every block is given back to the callback
+        assert_eq!(empty.try_iter().count(), 4);
+    }
+
+    #[test]
+    fn mixes_down_any_sample_format() {
+        let mut mono = Vec::with_capacity(8);
+        mix_down(&[0.5f32, -0.5, 1.0, 0.0], 2, &mut mono);
+        assert_eq!(mono, [0.0, 0.5]);
+        mix_down(&[i16::MIN, 0, 16384], 1, &mut mono);
+        assert_eq!(mono, [0.0, 0.5, -1.0, 0.0, 0.5]);
+        // blocks are filled within the capacity they were allocated with
+        assert_eq!(mono.capacity(), 8);
+    }
+}
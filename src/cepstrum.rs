@@ -0,0 +1,39 @@
+use std::ops::Range;
+
+use realfft::ComplexToReal;
+use rustfft::{num_complex::Complex, num_traits::Float, FftNum};
+
+use crate::{compare, float};
+
+/// Period of the fundamental in samples according to the real cepstrum.
+///
+/// The inverse FFT of the log-magnitude spectrum turns the evenly spaced harmonics
+/// into a single peak at the period of the fundamental, while the spectral envelope,
+/// e.g. the formants of speech, stays at the lowest quefrencies.
+/// Only periods within `quefrencies` are considered, refined with a parabola.
+/// Returns `None` if `quefrencies` is empty.
+pub(crate) fn cepstrum_peak<T: FftNum + Float>(
+    magnitudes: &[T],
+    inverse_fft: &dyn ComplexToReal<T>,
+    quefrencies: Range<usize>,
+) -> Option<T> {
+    let mut log_spectrum = magnitudes
+        .iter()
+        .map(|m| Complex::new(m.max(T::min_positive_value()).ln(), T::zero()))
+        .collect::<Vec<_>>();
+    let mut cepstrum = inverse_fft.make_output_vec();
+    inverse_fft
+        .process(&mut log_spectrum, &mut cepstrum)
+        .expect("log spectrum to be real");
+
+    let quefrencies = quefrencies.start.max(1)..quefrencies.end.min(cepstrum.len() / 2);
+    let peak = quefrencies.max_by(|&q1, &q2| compare(cepstrum[q1], cepstrum[q2]))?;
+    let (a, b, c) = (cepstrum[peak - 1], cepstrum[peak], cepstrum[peak + 1]);
+    let curvature = a - float::<T>(2.0) * b + c;
+    let offset = if curvature < T::zero() {
+        float::<T>(0.5) * (a - c) / curvature
+    } else {
+        T::zero()
+    };
+    Some(float::<T>(peak as f64) + offset)
+}
@@ -0,0 +1,235 @@
+use std::f64::consts::TAU;
+
+use rustfft::{
+    num_complex::Complex,
+    num_traits::{Float, Zero},
+    FftNum,
+};
+
+use crate::{check_finite, compare, float, DetectError, DetectorCreateError};
+
+/// Spectral detector with logarithmically spaced bins, the constant-Q transform
+///
+/// Bins are spaced by a fixed fraction of an octave from `min_freq` to `max_freq`,
+/// e.g. 12 per octave for semitones, instead of the constant `sample_rate / sample_count`
+/// of [crate::FreqDetector]. Low notes get many more bins than with a linear spectrum,
+/// and the buckets above the notes of interest are not computed at all.
+///
+/// Every bin correlates the samples with a Hann-windowed complex tone spanning as many
+/// periods as the bins per octave need to tell neighbors apart, centered in the samples.
+/// Bins needing more than `sample_count` samples use all of them, so they get wider
+/// than their spacing but still refine the peak finely, like a zoomed FFT.
+/// Detection takes time proportional to the number of bins times `sample_count`.
+///
+/// ```
+/// use freq_det::ConstantQDetector;
+///
+/// // quarter-tones over the range of a bass guitar
+/// let detector = ConstantQDetector::new(44100, 4096, 30.0, 400.0, 24).unwrap();
+/// # let samples = vec![0.0; 4096];
+/// if let Some(freq) = detector.detect(&samples).unwrap() {
+///     println!("{freq:.2} Hz");
+/// }
+/// ```
+pub struct ConstantQDetector<T = f32> {
+    sample_count: usize,
+    frequencies: Vec<T>,
+    /// First sample every kernel is applied to
+    offsets: Vec<usize>,
+    kernels: Vec<Vec<Complex<T>>>,
+}
+
+impl<T: FftNum + Float> ConstantQDetector<T> {
+    /// Bins start at `min_freq` and are `1 / bins_per_octave` octaves apart,
+    /// the last one is at or below `max_freq`.
+    ///
+    /// # Errors
+    /// - if sample rate is 0
+    /// - if fewer than 4 samples are passed
+    /// - if `min_freq` is not positive or not below `max_freq`
+    /// - if `max_freq` is above the Nyquist frequency
+    /// - if `bins_per_octave` is 0
+    pub fn new(
+        sample_rate: usize,
+        sample_count: usize,
+        min_freq: T,
+        max_freq: T,
+        bins_per_octave: usize,
+    ) -> Result<Self, DetectorCreateError> {
+        if sample_rate < 1 {
+            return Err(DetectorCreateError::SampleRateTooLow);
+        }
+        if sample_count < 4 {
+            return Err(DetectorCreateError::TooFewSamples);
+        }
+        if !(min_freq > T::zero() && min_freq < max_freq) {
+            return Err(DetectorCreateError::EmptyFreqRange);
+        }
+        if max_freq > float(sample_rate as f64 / 2.0) {
+            return Err(DetectorCreateError::FreqRangeAboveNyquist);
+        }
+        if bins_per_octave < 1 {
+            return Err(DetectorCreateError::TooFewBinsPerOctave);
+        }
+
+        let octaves = (max_freq / min_freq).log2().to_f64().unwrap_or(0.0);
+        let bin_count = (octaves * bins_per_octave as f64 + 1e-9).floor() as usize + 1;
+        // bandwidth over center frequency, which is the spacing of the bins
+        let q = 1.0 / (2f64.powf(1.0 / bins_per_octave as f64) - 1.0);
+        let min_freq_f64 = min_freq.to_f64().unwrap_or(0.0);
+        let mut frequencies = Vec::with_capacity(bin_count);
+        let mut offsets = Vec::with_capacity(bin_count);
+        let mut kernels = Vec::with_capacity(bin_count);
+        for bin in 0..bin_count {
+            let freq = min_freq_f64 * 2f64.powf(bin as f64 / bins_per_octave as f64);
+            let len = ((q * sample_rate as f64 / freq).ceil() as usize).clamp(1, sample_count);
+            let window = (0..len)
+                .map(|n| 0.5 - 0.5 * (TAU * (n as f64 + 0.5) / len as f64).cos())
+                .collect::<Vec<_>>();
+            let window_sum = window.iter().sum::<f64>();
+            let kernel = window
+                .iter()
+                .enumerate()
+                .map(|(n, w)| {
+                    let phase = -TAU * freq * n as f64 / sample_rate as f64;
+                    Complex::new(
+                        float(w * phase.cos() / window_sum),
+                        float(w * phase.sin() / window_sum),
+                    )
+                })
+                .collect();
+            frequencies.push(float(freq));
+            offsets.push((sample_count - len) / 2);
+            kernels.push(kernel);
+        }
+        Ok(Self {
+            sample_count,
+            frequencies,
+            offsets,
+            kernels,
+        })
+    }
+
+    /// Magnitude of every bin, in the order of [Self::frequencies].
+    /// A full-scale tone at the center of a bin reads `0.5`.
+    ///
+    /// # Errors
+    ///
+    /// - if `samples.len()` does not match the `sample_count` passed to [Self::new]
+    /// - if there are `NaN`s or infinities in the sample slice
+    pub fn magnitudes(&self, samples: &[T]) -> Result<Vec<T>, DetectError> {
+        if samples.len() != self.sample_count {
+            return Err(DetectError::SampleCountMismatch {
+                expected: self.sample_count,
+                passed: samples.len(),
+            });
+        }
+        check_finite(samples)?;
+        let magnitudes = self
+            .kernels
+            .iter()
+            .zip(&self.offsets)
+            .map(|(kernel, &offset)| {
+                kernel
+                    .iter()
+                    .zip(&samples[offset..])
+                    .fold(Complex::zero(), |sum: Complex<T>, (k, s)| sum + *k * *s)
+                    .norm()
+            })
+            .collect::<Vec<_>>();
+        if magnitudes.iter().any(|m| m.is_nan()) {
+            return Err(DetectError::NansFound);
+        }
+        Ok(magnitudes)
+    }
+
+    /// Frequency of the strongest bin, refined between the bins,
+    /// `None` if the samples are silent
+    ///
+    /// # Errors
+    ///
+    /// Same as [Self::magnitudes]
+    pub fn detect(&self, samples: &[T]) -> Result<Option<T>, DetectError> {
+        let magnitudes = self.magnitudes(samples)?;
+        let Some((peak, &magnitude)) = magnitudes
+            .iter()
+            .enumerate()
+            .max_by(|(_, a), (_, b)| compare(**a, **b))
+        else {
+            return Ok(None);
+        };
+        if magnitude <= T::zero() {
+            return Ok(None);
+        }
+        if peak == 0 || peak + 1 == magnitudes.len() {
+            return Ok(Some(self.frequencies[peak]));
+        }
+        // vertex of the parabola through the logarithms over frequency, which fits the
+        // main lobe of the Hann window well, the bins are not evenly spaced there
+        let ln = |m: T| m.max(T::min_positive_value()).ln();
+        let (x1, x2, x3) = (
+            self.frequencies[peak - 1],
+            self.frequencies[peak],
+            self.frequencies[peak + 1],
+        );
+        let (y1, y2, y3) = (
+            ln(magnitudes[peak - 1]),
+            ln(magnitude),
+            ln(magnitudes[peak + 1]),
+        );
+        let numerator = (x2 - x1).powi(2) * (y2 - y3) - (x2 - x3).powi(2) * (y2 - y1);
+        let denominator = (x2 - x1) * (y2 - y3) - (x2 - x3) * (y2 - y1);
+        if denominator == T::zero() {
+            return Ok(Some(x2));
+        }
+        let vertex = x2 - float::<T>(0.5) * numerator / denominator;
+        Ok(Some(vertex.max(x1).min(x3)))
+    }
+
+    /// Center frequency of every bin
+    pub fn frequencies(&self) -> &[T] {
+        &self.frequencies
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::DetectorCreateError;
+
+    use super::ConstantQDetector;
+
+    #[test]
+    fn resolves_low_notes_finely() {
+        use std::f32::consts::TAU;
+        let sample_count = 4096;
+        let detector = ConstantQDetector::new(44100, sample_count, 27.5, 4000.0, 48).unwrap();
+        assert_eq!(detector.frequencies()[48], 55.0);
+        assert!(*detector.frequencies().last().unwrap() <= 4000.0);
+
+        let tone = |freq: f32| {
+            (0..sample_count)
+                .map(|i| 0.8 * (i as f32 / 44100.0 * freq * TAU).sin())
+                .collect::<Vec<f32>>()
+        };
+        // bins 0.4-1.5 Hz apart here, the buckets of a linear spectrum 10.8 Hz
+        let mut max_error = 0.0f32;
+        for i in 0..40 {
+            let freq = 30.0 + i as f32 * 1.77;
+            let detected = detector.detect(&tone(freq)).unwrap().unwrap();
+            max_error = max_error.max((detected - freq).abs());
+        }
+        assert!(max_error < 0.05, "{max_error}");
+        let detected = detector.detect(&tone(1318.5)).unwrap().unwrap();
+        assert!((detected - 1318.5).abs() < 0.5, "{detected}");
+
+        assert_eq!(detector.detect(&vec![0.0; sample_count]).unwrap(), None);
+        assert!(matches!(
+            ConstantQDetector::new(44100, sample_count, 100.0, 30000.0, 12),
+            Err(DetectorCreateError::FreqRangeAboveNyquist)
+        ));
+        assert!(matches!(
+            ConstantQDetector::new(44100, sample_count, 100.0, 1000.0, 0),
+            Err(DetectorCreateError::TooFewBinsPerOctave)
+        ));
+    }
+}
@@ -0,0 +1,217 @@
+use crate::{DetectError, DetectorCreateError, GoertzelDetector};
+
+const LOW_GROUP: [f32; 4] = [697.0, 770.0, 852.0, 941.0];
+const HIGH_GROUP: [f32; 4] = [1209.0, 1336.0, 1477.0, 1633.0];
+const KEYS: [[char; 4]; 4] = [
+    ['1', '2', '3', 'A'],
+    ['4', '5', '6', 'B'],
+    ['7', '8', '9', 'C'],
+    ['*', '0', '#', 'D'],
+];
+
+/// How many times the strongest tone of a group has to be more powerful than the others
+const MIN_GROUP_DOMINANCE: f32 = 4.0;
+/// Minimum share of the signal energy that each of the two tones has to carry
+const MIN_TONE_SHARE: f32 = 0.1;
+/// Power of the high group tone relative to the low group one, lines attenuate
+/// higher frequencies more, so the high tone may be up to 8 dB weaker or 4 dB stronger
+const TWIST_RANGE: (f32, f32) = (0.158, 2.512);
+
+/// Decodes touch-tone (DTMF) key presses
+///
+/// 205 samples at 8000 Hz is the classic choice,
+/// long enough to tell the tones of a group apart.
+pub struct DtmfDecoder {
+    low_group: GoertzelDetector<f32>,
+    high_group: GoertzelDetector<f32>,
+}
+
+impl DtmfDecoder {
+    /// # Errors
+    /// - if sample rate is 0 or too low to contain the DTMF tones
+    /// - if no samples are passed
+    pub fn new(sample_rate: usize, sample_count: usize) -> Result<Self, DetectorCreateError> {
+        Ok(Self {
+            low_group: GoertzelDetector::new(sample_rate, sample_count, &LOW_GROUP)?,
+            high_group: GoertzelDetector::new(sample_rate, sample_count, &HIGH_GROUP)?,
+        })
+    }
+
+    /// The pressed key, `None` unless exactly one tone of each group clearly dominates
+    /// and together they make up most of the signal. The high group tone may be up to
+    /// 8 dB weaker or 4 dB stronger than the low group one, as in ITU-T Q.24.
+    ///
+    /// # Errors
+    ///
+    /// - if `samples.len()` does not match the `sample_count` passed to [Self::new]
+    /// - if there are `NaN`s or infinities in the sample slice
+    pub fn decode(&self, samples: &[f32]) -> Result<Option<char>, DetectError> {
+        // power of a full-scale tone is (amplitude * N / 2)², its energy is amplitude² * N / 2
+        let energy = samples.iter().map(|s| s * s).sum::<f32>() * samples.len() as f32 / 2.0;
+        let dominant = |powers: Vec<f32>| {
+            let (index, &strongest) = powers
+                .iter()
+                .enumerate()
+                .max_by(|(_, p1), (_, p2)| p1.total_cmp(p2))?;
+            let dominates = powers
+                .iter()
+                .enumerate()
+                .all(|(i, p)| i == index || p * MIN_GROUP_DOMINANCE < strongest);
+            (dominates && strongest > MIN_TONE_SHARE * energy).then_some((index, strongest))
+        };
+
+        let low = dominant(self.low_group.powers(samples)?);
+        let high = dominant(self.high_group.powers(samples)?);
+        Ok(low.zip(high).and_then(|((row, low), (column, high))| {
+            let twist = high / low;
+            (twist >= TWIST_RANGE.0 && twist <= TWIST_RANGE.1).then_some(KEYS[row][column])
+        }))
+    }
+}
+
+/// Decodes key presses from a continuous stream of samples
+///
+/// The stream is cut into consecutive blocks of `sample_count` samples, and a key
+/// counts as pressed or released once two blocks in a row agree on it. That way
+/// a key held down for many blocks is reported once, and short glitches are ignored.
+/// Keys need to be held at least three blocks to be reliably detected, e.g. 77 ms
+/// for 205 samples at 8000 Hz.
+pub struct DtmfStream {
+    decoder: DtmfDecoder,
+    sample_count: usize,
+    buffer: Vec<f32>,
+    candidate: Option<char>,
+    pressed: Option<char>,
+}
+
+impl DtmfStream {
+    /// # Errors
+    /// Same as [DtmfDecoder::new]
+    pub fn new(sample_rate: usize, sample_count: usize) -> Result<Self, DetectorCreateError> {
+        Ok(Self {
+            decoder: DtmfDecoder::new(sample_rate, sample_count)?,
+            sample_count,
+            buffer: Vec::with_capacity(sample_count),
+            candidate: None,
+            pressed: None,
+        })
+    }
+
+    /// Appends `samples` of any length and returns the keys pressed in them, in order.
+    /// Blocks containing `NaN`s or infinities count as no key.
+    pub fn push(&mut self, samples: &[f32]) -> Vec<char> {
+        let mut keys = vec![];
+        for &sample in samples {
+            self.buffer.push(sample);
+            if self.buffer.len() < self.sample_count {
+                continue;
+            }
+            let key = self.decoder.decode(&self.buffer).unwrap_or(None);
+            self.buffer.clear();
+            if key == self.candidate && key != self.pressed {
+                self.pressed = key;
+                keys.extend(key);
+            }
+            self.candidate = key;
+        }
+        keys
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DtmfDecoder, DtmfStream, HIGH_GROUP, KEYS, LOW_GROUP};
+
+    fn tones(freqs: &[f32], sample_count: usize) -> Vec<f32> {
+        use std::f32::consts::TAU;
+        (0..sample_count)
+            .map(|i| {
+                freqs
+                    .iter()
+                    .map(|f| 0.5 * (i as f32 / 8000.0 * f * TAU).sin())
+                    .sum()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn decodes_every_key() {
+        let sample_count = 205;
+        let decoder = DtmfDecoder::new(8000, sample_count).unwrap();
+        for (row, low) in LOW_GROUP.iter().enumerate() {
+            for (column, high) in HIGH_GROUP.iter().enumerate() {
+                let samples = tones(&[*low, *high], sample_count);
+                assert_eq!(
+                    decoder.decode(&samples).unwrap(),
+                    Some(KEYS[row][column]),
+                    "{low} Hz + {high} Hz"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_non_dtmf_signals() {
+        let sample_count = 205;
+        let decoder = DtmfDecoder::new(8000, sample_count).unwrap();
+        assert_eq!(decoder.decode(&vec![0.0; sample_count]).unwrap(), None);
+        // a single tone
+        assert_eq!(
+            decoder.decode(&tones(&[697.0], sample_count)).unwrap(),
+            None
+        );
+        // two tones of the low group
+        let two_rows = tones(&[697.0, 852.0, 1209.0], sample_count);
+        assert_eq!(decoder.decode(&two_rows).unwrap(), None);
+        // a dial tone
+        assert_eq!(
+            decoder
+                .decode(&tones(&[350.0, 440.0], sample_count))
+                .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn rejects_excessive_twist() {
+        use std::f32::consts::TAU;
+        let sample_count = 205;
+        let decoder = DtmfDecoder::new(8000, sample_count).unwrap();
+        let twisted = |high_amplitude: f32| {
+            (0..sample_count)
+                .map(|i| {
+                    let t = i as f32 / 8000.0;
+                    0.5 * (t * 697.0 * TAU).sin() + high_amplitude * (t * 1209.0 * TAU).sin()
+                })
+                .collect::<Vec<f32>>()
+        };
+        // 6 dB weaker and 3 dB stronger are fine
+        assert_eq!(decoder.decode(&twisted(0.25)).unwrap(), Some('1'));
+        assert_eq!(decoder.decode(&twisted(0.7)).unwrap(), Some('1'));
+        // 12 dB weaker and 6 dB stronger are not
+        assert_eq!(decoder.decode(&twisted(0.125)).unwrap(), None);
+        assert_eq!(decoder.decode(&twisted(1.0)).unwrap(), None);
+    }
+
+    #[test]
+    fn stream_reports_every_press_once() {
+        // 100 ms presses and pauses, the same key twice in a row
+        let presses = "15509#";
+        let mut samples = vec![];
+        for key in presses.chars() {
+            let (row, column) = (0..4)
+                .flat_map(|row| (0..4).map(move |column| (row, column)))
+                .find(|(row, column)| KEYS[*row][*column] == key)
+                .unwrap();
+            samples.extend(tones(&[LOW_GROUP[row], HIGH_GROUP[column]], 800));
+            samples.extend(vec![0.0; 800]);
+        }
+
+        let mut stream = DtmfStream::new(8000, 205).unwrap();
+        let decoded = samples
+            .chunks(333)
+            .flat_map(|chunk| stream.push(chunk))
+            .collect::<String>();
+        assert_eq!(decoded, presses);
+    }
+}
@@ -0,0 +1,130 @@
+use std::{
+    panic::{catch_unwind, AssertUnwindSafe},
+    ptr, slice,
+};
+
+use crate::{DetectError, FreqDetector};
+
+/// Success, the error codes and their values never change between versions
+pub const FREQ_DET_OK: i32 = 0;
+pub const FREQ_DET_NULL_POINTER: i32 = -1;
+pub const FREQ_DET_SAMPLE_COUNT_MISMATCH: i32 = -2;
+pub const FREQ_DET_NANS_FOUND: i32 = -3;
+pub const FREQ_DET_NON_FINITE: i32 = -4;
+pub const FREQ_DET_EMPTY_SPECTRUM: i32 = -5;
+pub const FREQ_DET_NO_SUCH_CHANNEL: i32 = -6;
+pub const FREQ_DET_NO_CHANNELS: i32 = -7;
+pub const FREQ_DET_INCOMPLETE_FRAME: i32 = -8;
+pub const FREQ_DET_PANIC: i32 = -100;
+
+impl DetectError {
+    /// Error code of the C interface in `include/freq_det.h`, codes never change
+    fn code(&self) -> i32 {
+        match self {
+            DetectError::SampleCountMismatch { .. } => FREQ_DET_SAMPLE_COUNT_MISMATCH,
+            DetectError::NansFound => FREQ_DET_NANS_FOUND,
+            DetectError::NonFinite => FREQ_DET_NON_FINITE,
+            DetectError::EmptySpectrum => FREQ_DET_EMPTY_SPECTRUM,
+            DetectError::NoSuchChannel { .. } => FREQ_DET_NO_SUCH_CHANNEL,
+            DetectError::NoChannels => FREQ_DET_NO_CHANNELS,
+            DetectError::IncompleteFrame { .. } => FREQ_DET_INCOMPLETE_FRAME,
+        }
+    }
+}
+
+/// Creates an `f32` detector, see [FreqDetector::new].
+///
+/// Returns null if the parameters are invalid or creating it panicked.
+/// The detector has to be released with [freq_det_free].
+#[no_mangle]
+pub extern "C" fn freq_det_new(sample_rate: usize, sample_count: usize) -> *mut FreqDetector {
+    catch_unwind(|| FreqDetector::new(sample_rate, sample_count))
+        .ok()
+        .and_then(Result::ok)
+        .map_or(ptr::null_mut(), |detector| {
+            Box::into_raw(Box::new(detector))
+        })
+}
+
+/// Detects the frequency of `len` samples into `freq`, see [FreqDetector::detect_mut].
+///
+/// Returns [FREQ_DET_OK] on success, or another `FREQ_DET_*` code, in which case
+/// `freq` is left alone. Panics are caught and reported as [FREQ_DET_PANIC].
+///
+/// # Safety
+///
+/// `detector` has to come from [freq_det_new] and not be used from another thread
+/// at the same time, `samples` has to point to `len` floats and `freq` to a float.
+#[no_mangle]
+pub unsafe extern "C" fn freq_det_detect(
+    detector: *mut FreqDetector,
+    samples: *const f32,
+    len: usize,
+    freq: *mut f32,
+) -> i32 {
+    if detector.is_null() || samples.is_null() || freq.is_null() {
+        return FREQ_DET_NULL_POINTER;
+    }
+    let detector = &mut *detector;
+    let samples = slice::from_raw_parts(samples, len);
+    match catch_unwind(AssertUnwindSafe(|| detector.detect_mut(samples))) {
+        Ok(Ok(detected)) => {
+            *freq = detected;
+            FREQ_DET_OK
+        }
+        Ok(Err(error)) => error.code(),
+        Err(_) => FREQ_DET_PANIC,
+    }
+}
+
+/// Releases a detector created by [freq_det_new], does nothing for null.
+///
+/// # Safety
+///
+/// `detector` must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn freq_det_free(detector: *mut FreqDetector) {
+    if !detector.is_null() {
+        // dropping only frees memory, it does not panic
+        drop(Box::from_raw(detector));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ptr;
+
+    use super::{
+        freq_det_detect, freq_det_free, freq_det_new, FREQ_DET_NANS_FOUND, FREQ_DET_NULL_POINTER,
+        FREQ_DET_OK, FREQ_DET_SAMPLE_COUNT_MISMATCH,
+    };
+
+    #[test]
+    fn detects_through_the_c_interface() {
+        use std::f32::consts::TAU;
+        assert!(freq_det_new(0, 4096).is_null());
+
+        let detector = freq_det_new(44100, 4096);
+        assert!(!detector.is_null());
+        let mut samples = (0..4096)
+            .map(|i| (i as f32 / 44100.0 * 440.0 * TAU).sin())
+            .collect::<Vec<f32>>();
+        let mut freq = 0.0;
+        unsafe {
+            let code = freq_det_detect(detector, samples.as_ptr(), samples.len(), &mut freq);
+            assert_eq!(code, FREQ_DET_OK);
+            assert!((freq - 440.0).abs() < 0.5, "{freq}");
+
+            let code = freq_det_detect(detector, samples.as_ptr(), 100, &mut freq);
+            assert_eq!(code, FREQ_DET_SAMPLE_COUNT_MISMATCH);
+            samples[10] = f32::NAN;
+            let code = freq_det_detect(detector, samples.as_ptr(), samples.len(), &mut freq);
+            assert_eq!(code, FREQ_DET_NANS_FOUND);
+            let code = freq_det_detect(ptr::null_mut(), samples.as_ptr(), 4096, &mut freq);
+            assert_eq!(code, FREQ_DET_NULL_POINTER);
+
+            freq_det_free(detector);
+            freq_det_free(ptr::null_mut());
+        }
+    }
+}
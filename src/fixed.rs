@@ -0,0 +1,177 @@
+use alloc::{vec, vec::Vec};
+
+use crate::{DetectError, DetectorCreateError};
+
+/// `1.0` in Q15, the format of the normalized difference
+const Q15_ONE: u64 = 1 << 15;
+/// Fractional bits of the refined period
+const PERIOD_FRACTION_BITS: u32 = 8;
+
+/// Same as [crate::YinDetector], for `i16` samples and with integer math only
+///
+/// Meant for microcontrollers without a floating-point unit. The normalized difference
+/// is computed in Q15 and the period is refined to `1/256` of a sample, so frequencies
+/// are within a few hundredths of a percent of the floating-point [crate::YinDetector].
+/// The lowest detectable frequency is `2 * sample_rate / sample_count`.
+///
+/// ```
+/// use freq_det::FixedPointYin;
+///
+/// // threshold of 0.1 in Q15
+/// let detector = FixedPointYin::new(8000, 512, 3277).unwrap();
+/// # let samples_from_adc = vec![0i16; 512];
+/// if let Some(millihertz) = detector.detect(&samples_from_adc).unwrap() {
+///     println!("{}.{:03} Hz", millihertz / 1000, millihertz % 1000);
+/// }
+/// ```
+pub struct FixedPointYin {
+    sample_rate: u32,
+    sample_count: usize,
+    threshold: u16,
+}
+
+impl FixedPointYin {
+    /// `threshold` is the cutoff of the normalized difference in Q15,
+    /// `3277`-`4915` (`0.1`-`0.15`) work well.
+    ///
+    /// # Errors
+    /// - if sample rate is 0
+    /// - if fewer than 4 samples are passed
+    /// - if `threshold` is not between `0` and `32768` (`1.0`)
+    pub fn new(
+        sample_rate: u32,
+        sample_count: usize,
+        threshold: u16,
+    ) -> Result<Self, DetectorCreateError> {
+        if sample_rate < 1 {
+            return Err(DetectorCreateError::SampleRateTooLow);
+        }
+        if sample_count < 4 {
+            return Err(DetectorCreateError::TooFewSamples);
+        }
+        if threshold == 0 || u64::from(threshold) >= Q15_ONE {
+            return Err(DetectorCreateError::InvalidThreshold);
+        }
+        Ok(Self {
+            sample_rate,
+            sample_count,
+            threshold,
+        })
+    }
+
+    /// Frequency in millihertz, `None` if no pitch was found, e.g. for noise or silence
+    ///
+    /// # Errors
+    ///
+    /// - if `samples.len()` does not match the `sample_count` passed to [Self::new]
+    pub fn detect(&self, samples: &[i16]) -> Result<Option<u32>, DetectError> {
+        if samples.len() != self.sample_count {
+            return Err(DetectError::SampleCountMismatch {
+                expected: self.sample_count,
+                passed: samples.len(),
+            });
+        }
+        let normalized = self.normalized_difference(samples);
+
+        let threshold = u64::from(self.threshold);
+        let Some(mut period) = (1..normalized.len()).find(|&tau| normalized[tau] < threshold)
+        else {
+            return Ok(None);
+        };
+        // walk down to the bottom of the dip
+        while period + 1 < normalized.len() && normalized[period + 1] < normalized[period] {
+            period += 1;
+        }
+
+        let period = refine_minimum(&normalized, period);
+        let millihertz = ((u64::from(self.sample_rate) * 1000) << PERIOD_FRACTION_BITS) / period;
+        Ok(Some(millihertz.min(u64::from(u32::MAX)) as u32))
+    }
+
+    /// Cumulative mean normalized difference in Q15 for every lag up to half of the samples
+    fn normalized_difference(&self, samples: &[i16]) -> Vec<u64> {
+        let window = self.sample_count / 2;
+        let mut normalized = vec![Q15_ONE; window];
+        let mut running_sum = 0u64;
+        for tau in 1..window {
+            let difference = (0..window).fold(0u64, |sum, j| {
+                let delta = i32::from(samples[j]) - i32::from(samples[j + tau]);
+                sum + u64::from(delta.unsigned_abs()).pow(2)
+            });
+            running_sum += difference;
+            if running_sum > 0 {
+                // wide enough for the longest windows at full scale
+                let scaled = u128::from(difference) * tau as u128 * u128::from(Q15_ONE);
+                normalized[tau] = (scaled / u128::from(running_sum)) as u64;
+            }
+        }
+        normalized
+    }
+}
+
+/// Position of the minimum of a parabola through `tau` and its neighbors,
+/// with [PERIOD_FRACTION_BITS] fractional bits
+fn refine_minimum(values: &[u64], tau: usize) -> u64 {
+    let tau_fixed = (tau as u64) << PERIOD_FRACTION_BITS;
+    if tau < 1 || tau + 1 >= values.len() {
+        return tau_fixed;
+    }
+    let (a, b, c) = (
+        values[tau - 1] as i64,
+        values[tau] as i64,
+        values[tau + 1] as i64,
+    );
+    let curvature = a - 2 * b + c;
+    if curvature <= 0 {
+        return tau_fixed;
+    }
+    // tau + 0.5 * (a - c) / curvature
+    let offset = ((a - c) << PERIOD_FRACTION_BITS) / (2 * curvature);
+    (tau_fixed as i64 + offset) as u64
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use crate::{DetectError, DetectorCreateError, YinDetector};
+
+    use super::FixedPointYin;
+
+    #[test]
+    fn detects_pitch_without_floats() {
+        use std::f32::consts::TAU;
+        let sample_count = 1024;
+        let detector = FixedPointYin::new(8000, sample_count, 3277).unwrap();
+        let float_detector = YinDetector::new(8000, sample_count, 0.1).unwrap();
+
+        for freq in [82.41, 220.0, 659.25] {
+            // a louder octave, as with a plucked string
+            let samples = (0..sample_count)
+                .map(|i| {
+                    let t = i as f32 / 8000.0;
+                    0.3 * (t * freq * TAU).sin() + 0.6 * (t * 2.0 * freq * TAU).sin()
+                })
+                .collect::<Vec<f32>>();
+            let quantized = samples
+                .iter()
+                .map(|s| (s * 32767.0) as i16)
+                .collect::<Vec<_>>();
+            let detected = detector.detect(&quantized).unwrap().unwrap();
+            let expected = (float_detector.detect(&samples).unwrap().unwrap() * 1000.0) as u32;
+            assert!(
+                detected.abs_diff(expected) < 100,
+                "detected {detected} expected {expected}"
+            );
+            assert!(detected.abs_diff((freq * 1000.0) as u32) < 3000);
+        }
+
+        assert_eq!(detector.detect(&vec![0; sample_count]).unwrap(), None);
+        assert!(matches!(
+            detector.detect(&[0; 10]),
+            Err(DetectError::SampleCountMismatch { .. })
+        ));
+        assert!(matches!(
+            FixedPointYin::new(8000, sample_count, 40000),
+            Err(DetectorCreateError::InvalidThreshold)
+        ));
+    }
+}
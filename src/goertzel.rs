@@ -0,0 +1,133 @@
+use std::f64::consts::TAU;
+
+use rustfft::{num_traits::Float, FftNum};
+
+use crate::{check_finite, compare, float, DetectError, DetectorCreateError};
+
+/// Measures the power at a handful of known frequencies with the Goertzel algorithm
+///
+/// Much cheaper than a full FFT when only a few frequencies matter,
+/// and works for any `sample_count`, not just FFT-friendly ones.
+pub struct GoertzelDetector<T = f32> {
+    sample_count: usize,
+    target_freqs: Vec<T>,
+    /// `2 * cos(ω)` for every target frequency
+    coefficients: Vec<T>,
+}
+
+impl<T: FftNum + Float> GoertzelDetector<T> {
+    /// # Errors
+    /// - if sample rate is 0
+    /// - if no samples are passed
+    /// - if any of `target_freqs` is negative or above the Nyquist frequency
+    pub fn new(
+        sample_rate: usize,
+        sample_count: usize,
+        target_freqs: &[T],
+    ) -> Result<Self, DetectorCreateError> {
+        if sample_rate < 1 {
+            return Err(DetectorCreateError::SampleRateTooLow);
+        }
+        if sample_count < 1 {
+            return Err(DetectorCreateError::TooFewSamples);
+        }
+        let nyquist = float::<T>(sample_rate as f64 / 2.0);
+        if !target_freqs
+            .iter()
+            .all(|f| *f >= T::zero() && *f <= nyquist)
+        {
+            return Err(DetectorCreateError::InvalidTargetFreq);
+        }
+        let coefficients = target_freqs
+            .iter()
+            .map(|f| {
+                let omega = TAU * f.to_f64().unwrap_or(0.0) / sample_rate as f64;
+                float(2.0 * omega.cos())
+            })
+            .collect();
+        Ok(Self {
+            sample_count,
+            target_freqs: target_freqs.to_vec(),
+            coefficients,
+        })
+    }
+
+    /// Squared DFT magnitude at every target frequency, in the order
+    /// they were passed to [Self::new]
+    ///
+    /// # Errors
+    ///
+    /// - if `samples.len()` does not match the `sample_count` passed to [Self::new]
+    /// - if there are `NaN`s or infinities in the sample slice
+    pub fn powers(&self, samples: &[T]) -> Result<Vec<T>, DetectError> {
+        if samples.len() != self.sample_count {
+            return Err(DetectError::SampleCountMismatch {
+                expected: self.sample_count,
+                passed: samples.len(),
+            });
+        }
+        check_finite(samples)?;
+        let powers = self
+            .coefficients
+            .iter()
+            .map(|&coefficient| {
+                let (s1, s2) = samples.iter().fold((T::zero(), T::zero()), |(s1, s2), x| {
+                    (*x + coefficient * s1 - s2, s1)
+                });
+                s1 * s1 + s2 * s2 - coefficient * s1 * s2
+            })
+            .collect::<Vec<_>>();
+        if powers.iter().any(|p| p.is_nan()) {
+            return Err(DetectError::NansFound);
+        }
+        Ok(powers)
+    }
+
+    /// Target frequency with the biggest power,
+    /// `None` if there are no target frequencies or the samples are silent
+    ///
+    /// # Errors
+    ///
+    /// Same as [Self::powers]
+    pub fn strongest(&self, samples: &[T]) -> Result<Option<T>, DetectError> {
+        Ok(self
+            .powers(samples)?
+            .into_iter()
+            .zip(&self.target_freqs)
+            .filter(|(power, _)| *power > T::zero())
+            .max_by(|(p1, _), (p2, _)| compare(*p1, *p2))
+            .map(|(_, freq)| *freq))
+    }
+
+    /// Frequencies passed to [Self::new]
+    pub fn target_freqs(&self) -> &[T] {
+        &self.target_freqs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::GoertzelDetector;
+
+    #[test]
+    fn goertzel_finds_present_tones() {
+        use std::f32::consts::TAU;
+        let sample_count = 205;
+        let samples = (0..sample_count)
+            .map(|i| {
+                (i as f32 / 8000.0 * 697.0 * TAU).sin()
+                    + 0.5 * (i as f32 / 8000.0 * 1209.0 * TAU).sin()
+            })
+            .collect::<Vec<f32>>();
+
+        let detector = GoertzelDetector::new(8000, sample_count, &[697.0, 770.0, 1209.0]).unwrap();
+        let powers = detector.powers(&samples).unwrap();
+        assert!(powers[0] > 10.0 * powers[1], "{powers:?}");
+        assert!(powers[2] > 10.0 * powers[1], "{powers:?}");
+        assert!(powers[0] > powers[2], "{powers:?}");
+        assert_eq!(detector.strongest(&samples).unwrap(), Some(697.0));
+
+        assert_eq!(detector.strongest(&vec![0.0; sample_count]).unwrap(), None);
+        assert!(GoertzelDetector::new(8000, sample_count, &[5000.0f32]).is_err());
+    }
+}
@@ -0,0 +1,133 @@
+use std::ops::Range;
+
+use rustfft::num_traits::Float;
+
+use crate::compare;
+
+/// Bucket of the fundamental according to the Harmonic Product Spectrum.
+///
+/// The spectrum is downsampled by `1..=harmonics` and the copies are multiplied,
+/// so the harmonics of the fundamental line up and reinforce it.
+/// The product is taken as a sum of logarithms to avoid overflowing for many harmonics.
+/// Only fundamentals within `candidates` are considered.
+pub(crate) fn harmonic_product_spectrum<T: Float>(
+    magnitudes: &[T],
+    harmonics: usize,
+    candidates: Range<usize>,
+) -> usize {
+    let harmonics = harmonics.max(1);
+    let log_product = |bucket: usize| {
+        (1..=harmonics).fold(T::zero(), |sum, h| {
+            sum + harmonic_magnitude(magnitudes, bucket, h).ln()
+        })
+    };
+    // bucket 0 is DC, which is a harmonic of nothing
+    (candidates.start.max(1)..candidates.end.min(magnitudes.len().div_ceil(harmonics)))
+        .max_by(|&b1, &b2| compare(log_product(b1), log_product(b2)))
+        .unwrap_or(candidates.start)
+}
+
+/// Weight of every harmonic relative to the one below it in [subharmonic_summation],
+/// as proposed by Hermes (1988)
+const SUBHARMONIC_COMPRESSION: f64 = 0.84;
+
+/// Fundamental according to Sub-Harmonic Summation, as the bucket of its strongest
+/// harmonic and the number of that harmonic, to refine the peak where there is one.
+///
+/// Every candidate is scored by the weighted sum of the magnitudes at its first
+/// `harmonics` harmonics, so unlike in [harmonic_product_spectrum] a weak or missing
+/// fundamental does not zero the score. The weights fall off with the harmonic number,
+/// so the octave below the fundamental, which only collects every other harmonic,
+/// scores lower. Only fundamentals within `candidates` are considered.
+pub(crate) fn subharmonic_summation<T: Float>(
+    magnitudes: &[T],
+    harmonics: usize,
+    candidates: Range<usize>,
+) -> (usize, usize) {
+    let compression = T::from(SUBHARMONIC_COMPRESSION).unwrap_or_else(T::one);
+    let fitting_harmonics =
+        |bucket: usize| (1..=harmonics.max(1)).take_while(move |h| bucket * h < magnitudes.len());
+    let score = |bucket: usize| {
+        fitting_harmonics(bucket)
+            .fold((T::zero(), T::one()), |(sum, weight), h| {
+                let magnitude = harmonic_magnitude(magnitudes, bucket, h);
+                (sum + weight * magnitude, weight * compression)
+            })
+            .0
+    };
+    // bucket 0 is DC, which is a harmonic of nothing
+    let fundamental = (candidates.start.max(1)..candidates.end.min(magnitudes.len()))
+        .max_by(|&b1, &b2| compare(score(b1), score(b2)))
+        .unwrap_or(candidates.start);
+    fitting_harmonics(fundamental)
+        .map(|h| (strongest_bucket(magnitudes, fundamental, h), h))
+        .max_by(|(b1, _), (b2, _)| compare(magnitudes[*b1], magnitudes[*b2]))
+        .unwrap_or((fundamental, 1))
+}
+
+/// Magnitude of the `harmonic` of a fundamental within half a bucket of `bucket`.
+///
+/// That harmonic can be up to `harmonic / 2` buckets away from `bucket * harmonic`,
+/// so the strongest bucket in that range is taken instead of plain downsampling.
+fn harmonic_magnitude<T: Float>(magnitudes: &[T], bucket: usize, harmonic: usize) -> T {
+    magnitudes[strongest_bucket(magnitudes, bucket, harmonic)]
+}
+
+/// Bucket of the `harmonic` of a fundamental within half a bucket of `bucket`,
+/// see [harmonic_magnitude]
+fn strongest_bucket<T: Float>(magnitudes: &[T], bucket: usize, harmonic: usize) -> usize {
+    let center = bucket * harmonic;
+    let from = center
+        .saturating_sub(harmonic / 2)
+        .min(magnitudes.len() - 1);
+    let to = (center + harmonic / 2).min(magnitudes.len() - 1);
+    (from..=to)
+        .max_by(|&b1, &b2| compare(magnitudes[b1], magnitudes[b2]))
+        .unwrap_or(from)
+}
+
+/// Harmonics may deviate from integer multiples of the fundamental by this share of it
+const HARMONIC_TOLERANCE: f64 = 0.05;
+/// Candidates for the fundamental go down to the lowest peak divided by this
+const MAX_DIVISOR: usize = 8;
+
+/// Whether `freq` is close to the second or a higher harmonic of `fundamental`
+pub(crate) fn is_overtone<T: Float>(freq: T, fundamental: T) -> bool {
+    let Some(tolerance) = T::from(HARMONIC_TOLERANCE) else {
+        return false;
+    };
+    let harmonic = (freq / fundamental).round();
+    harmonic >= T::one() + T::one() && (freq / fundamental - harmonic).abs() <= tolerance
+}
+
+/// Fundamental whose integer multiples explain all of the `peaks`,
+/// an approximate greatest common divisor of their frequencies.
+///
+/// Candidates are the lowest peak divided by `1..=MAX_DIVISOR`, the highest one that has
+/// every peak close to one of its harmonics is refined with a least-squares fit.
+/// `None` if no candidate explains all of the peaks.
+pub(crate) fn harmonic_spacing<T: Float>(peaks: &[T]) -> Option<T> {
+    let tolerance = T::from(HARMONIC_TOLERANCE)?;
+    let lowest = peaks
+        .iter()
+        .copied()
+        .filter(|p| *p > T::zero())
+        .fold(T::infinity(), T::min);
+    if !lowest.is_finite() {
+        return None;
+    }
+    (1..=MAX_DIVISOR).find_map(|divisor| {
+        let candidate = lowest / T::from(divisor)?;
+        let mut weighted_sum = T::zero();
+        let mut squares_sum = T::zero();
+        for &peak in peaks {
+            let harmonic = (peak / candidate).round();
+            if harmonic < T::one() || (peak / candidate - harmonic).abs() > tolerance {
+                return None;
+            }
+            weighted_sum = weighted_sum + peak * harmonic;
+            squares_sum = squares_sum + harmonic * harmonic;
+        }
+        Some(weighted_sum / squares_sum)
+    })
+}
@@ -18,23 +18,390 @@
 //! let freq_detector = FreqDetector::new(44100, sample_count).unwrap();
 //! assert_eq!(freq_detector.detect(&sinusoid_440hz).unwrap().round(), 440.0);
 //! ```
+//!
+//! Without the default `std` feature the crate is `no_std` and needs `alloc`, and
+//! it only has the detectors of the `microfft` and `fixed-point` features.
+
+#![cfg_attr(not(any(test, feature = "std")), no_std)]
 
-use std::sync::Arc;
+extern crate alloc;
 
-use rustfft::{
-    num_complex::{Complex, ComplexFloat},
-    Fft, FftPlanner,
+#[cfg(feature = "std")]
+mod builder;
+#[cfg(feature = "cpal")]
+pub mod capture;
+#[cfg(feature = "std")]
+mod cepstrum;
+#[cfg(feature = "std")]
+mod cqt;
+#[cfg(feature = "std")]
+mod dtmf;
+#[cfg(feature = "ffi")]
+mod ffi;
+#[cfg(feature = "fixed-point")]
+mod fixed;
+#[cfg(feature = "std")]
+mod goertzel;
+#[cfg(feature = "std")]
+mod harmonic;
+#[cfg(feature = "microfft")]
+mod micro;
+#[cfg(feature = "midi")]
+mod midi;
+#[cfg(feature = "std")]
+mod note;
+#[cfg(feature = "std")]
+mod onset;
+#[cfg(feature = "std")]
+mod picker;
+#[cfg(feature = "python")]
+mod python;
+#[cfg(feature = "std")]
+mod resample;
+#[cfg(feature = "std")]
+mod segment;
+#[cfg(feature = "std")]
+mod sliding;
+#[cfg(feature = "std")]
+mod spectrogram;
+#[cfg(feature = "std")]
+mod streaming;
+#[cfg(feature = "std")]
+mod track;
+#[cfg(feature = "std")]
+mod tuner;
+#[cfg(feature = "std")]
+mod vibrato;
+#[cfg(feature = "wasm")]
+mod wasm;
+#[cfg(feature = "wav")]
+mod wav;
+#[cfg(feature = "std")]
+mod window;
+#[cfg(feature = "std")]
+mod yin;
+#[cfg(feature = "std")]
+mod zoom;
+
+#[cfg(feature = "std")]
+use std::{
+    cmp::Ordering,
+    ops::Range,
+    sync::{Arc, OnceLock},
 };
+
+#[cfg(feature = "std")]
+use realfft::{ComplexToReal, RealFftPlanner, RealToComplex};
+#[cfg(feature = "std")]
+use rustfft::{num_complex::Complex, num_traits::Float, Fft, FftNum, FftPlanner};
 use thiserror::Error;
+#[cfg(feature = "std")]
+use window::PeakOffsetTable;
+
+#[cfg(feature = "std")]
+pub use builder::{FreqDetectorBuilder, FreqDetectorConfig};
+#[cfg(feature = "std")]
+pub use cqt::ConstantQDetector;
+#[cfg(feature = "std")]
+pub use dtmf::{DtmfDecoder, DtmfStream};
+#[cfg(feature = "fixed-point")]
+pub use fixed::FixedPointYin;
+#[cfg(feature = "std")]
+pub use goertzel::GoertzelDetector;
+#[cfg(feature = "wav")]
+pub use hound;
+#[cfg(feature = "microfft")]
+pub use micro::MicroFftDetector;
+#[cfg(feature = "midi")]
+pub use midi::{midi_messages, save_midi, write_midi, MidiMessage, NOTE_VELOCITY};
+#[cfg(feature = "std")]
+pub use note::{cents_off, freq_to_note, freq_to_note_with_reference, Note, NoteName, A4_FREQ};
+#[cfg(feature = "std")]
+pub use onset::{Onset, OnsetDetector};
+#[cfg(feature = "std")]
+pub use picker::{LowestStrongPicker, NoiseFloorPicker, PeakPicker, StrongestPicker};
+#[cfg(feature = "python")]
+pub use python::PyFreqDetector;
+#[cfg(feature = "std")]
+pub use realfft;
+#[cfg(feature = "std")]
+pub use resample::Resampler;
+#[cfg(feature = "std")]
+pub use segment::NoteEvent;
+#[cfg(feature = "std")]
+pub use sliding::SlidingDetector;
+#[cfg(feature = "std")]
+pub use spectrogram::{Spectrogram, SpectrogramScale};
+#[cfg(feature = "std")]
+pub use streaming::StreamingDetector;
+#[cfg(feature = "std")]
+pub use track::{PitchFrame, PitchTrack};
+#[cfg(feature = "std")]
+pub use tuner::{Tuner, TunerReading};
+#[cfg(feature = "std")]
+pub use vibrato::{vibrato, Vibrato};
+#[cfg(feature = "wasm")]
+pub use wasm::{WasmDetection, WasmFreqDetector};
+#[cfg(feature = "wav")]
+pub use wav::{WavDetections, WavError, WavSamples};
+#[cfg(feature = "std")]
+pub use window::WindowFunction;
+#[cfg(feature = "std")]
+pub use yin::YinDetector;
+#[cfg(feature = "std")]
+pub use zoom::ZoomDetector;
+
+/// How the peak frequency is refined between FFT buckets
+///
+/// Whatever the interpolation, a tone within 0.01 buckets of the midpoint between
+/// the two biggest adjacent buckets of its peak, judging by the magnitudes of the two,
+/// is considered to be right between them and is reported at the midpoint.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Interpolation {
+    /// Weighted average of the two biggest adjacent buckets,
+    /// corrected for the window's main lobe
+    #[default]
+    WeightedAverage,
+    /// Parabola fitted through the log-magnitudes of the peak bucket and its two neighbors.
+    ///
+    /// Works well with tapered windows, but is noticeably biased with
+    /// [WindowFunction::Rectangular].
+    Parabolic,
+    /// Center of the strongest bucket, or the midpoint of two tied ones
+    None,
+}
+
+/// Unit of the frequencies reported by the detection methods
+///
+/// All of them are proportional to each other, the peak is refined in buckets
+/// and only converted at the end.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OutputUnit {
+    #[default]
+    Hertz,
+    /// Fractional index of the FFT bucket, e.g. `512.3`
+    BucketIndex,
+    /// Cycles per sample, from `0.0` up to `0.5` at the Nyquist frequency
+    NormalizedCyclesPerSample,
+}
+
+/// How the frequency is picked from the samples
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DetectionMethod {
+    /// The strongest peak of the spectrum, as in [FreqDetector::detect]
+    #[default]
+    StrongestPeak,
+    /// The peak of the Harmonic Product Spectrum.
+    ///
+    /// Reports the fundamental of pitched sounds even when one of its harmonics
+    /// is louder, at the cost of not detecting frequencies above
+    /// `1 / harmonics` of the Nyquist frequency.
+    HarmonicProductSpectrum { harmonics: usize },
+    /// The fundamental whose first `harmonics` harmonics have the biggest weighted sum
+    /// of magnitudes, Sub-Harmonic Summation.
+    ///
+    /// More robust than [Self::HarmonicProductSpectrum] for instruments whose fundamental
+    /// is much weaker than the harmonics, e.g. bass notes of a piano, as a missing
+    /// harmonic lowers the sum instead of zeroing the product. Detects frequencies up
+    /// to the Nyquist frequency, using the harmonics that fit below it.
+    SubharmonicSummation { harmonics: usize },
+    /// The approximate greatest common divisor of the `peaks` strongest peaks.
+    ///
+    /// Reports the fundamental even when it is missing from the signal entirely,
+    /// e.g. filtered out by a telephone line, as long as its harmonics are there.
+    /// Peaks weaker than a tenth of the strongest one are ignored. Falls back to
+    /// the strongest peak if the peaks are not harmonics of a common fundamental.
+    HarmonicSpacing { peaks: usize },
+    /// Autocorrelation-based YIN in the time domain, see [YinDetector],
+    /// with a threshold of `0.1`.
+    ///
+    /// Does not mistake a strong harmonic for the fundamental, but ignores the window,
+    /// the frequency range and the other spectral options. Reports `0.0` if no pitch is
+    /// found. Takes time proportional to `sample_count²`.
+    Yin,
+    /// Peak of the real cepstrum, the inverse FFT of the log-magnitude spectrum.
+    ///
+    /// Robust for speech and other sounds whose spectral envelope makes some harmonics
+    /// much louder than others. Searches for fundamentals within the frequency range,
+    /// 50 to 1000 Hz if none is set.
+    Cepstrum,
+}
+
+/// Outcome of [FreqDetector::detect_pitch]
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Detection<T = f32> {
+    /// The peak is below the silence threshold, see [FreqDetector::set_silence_threshold]
+    Silent,
+    /// Frequency of the peak
+    Pitch(T),
+}
+
+/// Which channels of interleaved samples [FreqDetector::detect_interleaved] looks at
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ChannelMode {
+    /// The average of all of the channels, a single frequency
+    #[default]
+    Mix,
+    /// Only the channel with this index, a single frequency
+    Select(usize),
+    /// Every channel separately, one frequency per channel, see [FreqDetector::detect_channels]
+    PerChannel,
+}
+
+/// Everything [FreqDetector::detect_full] finds out about the strongest peak
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DetectionResult<T = f32> {
+    /// Refined frequency of the peak, `0.0` for silence
+    pub frequency: T,
+    /// Sum of the two adjacent buckets the peak is refined from,
+    /// see [FreqDetector::detect_with_magnitude]
+    pub magnitude: T,
+    /// Share of the spectral energy in the main lobe of the peak,
+    /// see [FreqDetector::detect_with_confidence]
+    pub confidence: T,
+    /// The peak is below the silence threshold, see [FreqDetector::set_silence_threshold]
+    pub is_silent: bool,
+    /// The strongest bucket of the peak, before refining
+    pub bucket: usize,
+    /// The peak is in the top 5% of the buckets, right below the Nyquist frequency.
+    ///
+    /// Such a frequency is unreliable and may well be an alias of a higher one,
+    /// which usually means the sample rate is too low for the signal.
+    pub near_nyquist: bool,
+}
 
+/// Detection of one window of [FreqDetector::detect_all]
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TimedDetection<T = f32> {
+    /// Index of the first sample of the window
+    pub sample: usize,
+    /// Start of the window in seconds
+    pub time: T,
+    /// Refined frequency of the peak, `0.0` for silence
+    pub frequency: T,
+    /// See [DetectionResult::confidence]
+    pub confidence: T,
+}
+
+#[cfg(feature = "std")]
 /// Frequency detector
-pub struct FreqDetector {
-    fft: Arc<dyn Fft<f32>>,
+///
+/// Works with `f32` samples by default, `FreqDetector::<f64>` gives double precision.
+///
+/// The detector is `Send + Sync`: every method taking `&self` allocates its own buffers,
+/// so one detector can be shared across a thread pool. Only [Self::detect_mut] reuses
+/// buffers, which is why it needs `&mut self`. Cloning is cheap, the clones share the
+/// planned FFT.
+pub struct FreqDetector<T: FftNum = f32> {
+    planner: RealFftPlanner<T>,
+    fft: Arc<dyn RealToComplex<T>>,
+    /// Planned on the first [FreqDetector::detect_complex]
+    complex_fft: OnceLock<Arc<dyn Fft<T>>>,
+    /// Planned on the first detection with [DetectionMethod::Cepstrum]
+    inverse_fft: OnceLock<Arc<dyn ComplexToReal<T>>>,
     sample_count: usize,
     sample_rate: usize,
+    window_function: WindowFunction,
+    window: Vec<T>,
+    peak_offsets: PeakOffsetTable<T>,
+    min_peak_separation: usize,
+    interpolation: Interpolation,
+    output_unit: OutputUnit,
+    freq_range: Option<(T, T)>,
+    remove_dc: bool,
+    pre_emphasis: Option<T>,
+    zero_pad_to: Option<usize>,
+    notch: Option<T>,
+    noise_floor_width: Option<usize>,
+    silence_threshold: T,
+    min_snr_db: Option<T>,
+    peak_picker: Arc<dyn PeakPicker<T>>,
+    scratch: Scratch<T>,
+}
+
+/// Buffers reused between detections by [FreqDetector::detect_mut]
+///
+/// Never reached through `&self`, which would break the `Sync` guarantee of [FreqDetector]
+#[cfg(feature = "std")]
+struct Scratch<T> {
+    fft_input: Vec<T>,
+    fft_output: Vec<Complex<T>>,
+    fft_scratch: Vec<Complex<T>>,
+    magnitudes: Vec<T>,
+    noise_floor: Vec<T>,
+    median_window: Vec<T>,
+    /// Lent to the peak picker, see [PeakPicker::pick_with_buffer],
+    /// and for the median of [FreqDetector::set_min_snr_db]
+    median_buffer: Vec<T>,
+}
+
+#[cfg(feature = "std")]
+impl<T> Default for Scratch<T> {
+    fn default() -> Self {
+        Self {
+            fft_input: vec![],
+            fft_output: vec![],
+            fft_scratch: vec![],
+            magnitudes: vec![],
+            noise_floor: vec![],
+            median_window: vec![],
+            median_buffer: vec![],
+        }
+    }
 }
 
-impl FreqDetector {
+#[cfg(feature = "std")]
+impl<T: FftNum> Scratch<T> {
+    fn new(fft: &dyn RealToComplex<T>) -> Self {
+        Self {
+            fft_input: fft.make_input_vec(),
+            fft_output: fft.make_output_vec(),
+            fft_scratch: fft.make_scratch_vec(),
+            magnitudes: Vec::with_capacity(fft.complex_len()),
+            noise_floor: Vec::with_capacity(fft.complex_len()),
+            median_window: vec![],
+            median_buffer: Vec::with_capacity(fft.complex_len()),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: FftNum> Clone for FreqDetector<T> {
+    fn clone(&self) -> Self {
+        Self {
+            planner: RealFftPlanner::new(),
+            fft: Arc::clone(&self.fft),
+            complex_fft: self.complex_fft.clone(),
+            inverse_fft: self.inverse_fft.clone(),
+            sample_count: self.sample_count,
+            sample_rate: self.sample_rate,
+            window_function: self.window_function,
+            window: self.window.clone(),
+            peak_offsets: self.peak_offsets.clone(),
+            min_peak_separation: self.min_peak_separation,
+            interpolation: self.interpolation,
+            output_unit: self.output_unit,
+            freq_range: self.freq_range,
+            remove_dc: self.remove_dc,
+            pre_emphasis: self.pre_emphasis,
+            zero_pad_to: self.zero_pad_to,
+            notch: self.notch,
+            noise_floor_width: self.noise_floor_width,
+            silence_threshold: self.silence_threshold,
+            min_snr_db: self.min_snr_db,
+            peak_picker: Arc::clone(&self.peak_picker),
+            scratch: Scratch::default(),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: FftNum + Float> FreqDetector<T> {
     /// `sample_rate` is `44100` for most modern applications
     ///
     /// `sample_count` numbers between `2048` and `8192` work well.
@@ -44,122 +411,2915 @@ impl FreqDetector {
     /// # Errors
     /// - if sample rate is 0
     /// - if fewer than 4 samples are passed
+    ///
+    /// See [FreqDetectorBuilder] for more options.
     pub fn new(sample_rate: usize, sample_count: usize) -> Result<Self, DetectorCreateError> {
-        let mut planner = FftPlanner::new();
-        if sample_rate < 1 {
-            return Err(DetectorCreateError::SampleRateTooLow);
+        FreqDetectorBuilder::new(sample_rate, sample_count).build()
+    }
+
+    /// Same as [Self::new], but multiplies the samples by `window` before the FFT.
+    ///
+    /// The window coefficients are computed once here, not on every detection.
+    ///
+    /// # Errors
+    /// - if sample rate is 0
+    /// - if fewer than 4 samples are passed
+    pub fn with_window(
+        sample_rate: usize,
+        sample_count: usize,
+        window: WindowFunction,
+    ) -> Result<Self, DetectorCreateError> {
+        FreqDetectorBuilder::new(sample_rate, sample_count)
+            .window(window)
+            .build()
+    }
+
+    /// Same as [Self::new], but uses an FFT planned in advance, `sample_count` is its length.
+    ///
+    /// Detection works on real samples, so the plan comes from a real-input
+    /// [realfft::RealFftPlanner], which is re-exported with a matching version.
+    /// See [FreqDetectorBuilder::build_with_fft] for more options.
+    ///
+    /// # Errors
+    /// - if sample rate is 0
+    /// - if the FFT is shorter than 4 samples
+    pub fn from_fft(
+        fft: Arc<dyn RealToComplex<T>>,
+        sample_rate: usize,
+    ) -> Result<Self, DetectorCreateError> {
+        FreqDetectorBuilder::new(sample_rate, fft.len()).build_with_fft(fft)
+    }
+
+    /// Creates a detector from parameters, e.g. loaded from a config file
+    ///
+    /// # Errors
+    /// Same as [FreqDetectorBuilder::build]
+    pub fn from_config(config: FreqDetectorConfig<T>) -> Result<Self, DetectorCreateError> {
+        FreqDetectorBuilder::from(config).build()
+    }
+
+    /// Parameters to create the same detector again with [Self::from_config],
+    /// except for the peak picker
+    pub fn config(&self) -> FreqDetectorConfig<T> {
+        FreqDetectorConfig {
+            sample_rate: self.sample_rate,
+            sample_count: self.sample_count,
+            window: self.window_function,
+            interpolation: self.interpolation,
+            output_unit: self.output_unit,
+            freq_range: self.freq_range,
+            remove_dc: self.remove_dc,
+            pre_emphasis: self.pre_emphasis,
+            zero_pad_to: self.zero_pad_to,
+            notch: self.notch,
+            noise_floor_width: self.noise_floor_width,
+            silence_threshold: self.silence_threshold,
+            min_snr_db: self.min_snr_db,
+        }
+    }
+
+    /// Shorthand for [FreqDetectorBuilder::new]
+    pub fn builder(sample_rate: usize, sample_count: usize) -> FreqDetectorBuilder<T> {
+        FreqDetectorBuilder::new(sample_rate, sample_count)
+    }
+
+    /// Changes how the peak is refined between buckets, [Interpolation::WeightedAverage] by default
+    pub fn set_interpolation(&mut self, interpolation: Interpolation) {
+        self.interpolation = interpolation;
+    }
+
+    /// Changes the unit of the detected frequencies, [OutputUnit::Hertz] by default.
+    ///
+    /// Applies to everything returning a frequency, except for [Self::spectrum]
+    /// and the bucket conversions, which always work in Hz.
+    pub fn set_output_unit(&mut self, output_unit: OutputUnit) {
+        self.output_unit = output_unit;
+    }
+
+    /// Whether to subtract the mean of the samples before the FFT, on by default.
+    ///
+    /// A DC bias puts a lot of energy into the lowest buckets,
+    /// which can outweigh low-frequency tones.
+    pub fn set_remove_dc(&mut self, remove_dc: bool) {
+        self.remove_dc = remove_dc;
+    }
+
+    /// Applies a first-order pre-emphasis filter `y[n] = x[n] - coefficient * x[n - 1]`
+    /// before windowing, off by default. `0.97` is a common choice for speech.
+    ///
+    /// The filter boosts high frequencies, so the harmonics of a voice are not drowned out
+    /// by its low-frequency energy. Detecting on a slice only filters the samples of that
+    /// window and keeps no state between calls, the first sample is left unfiltered.
+    /// [StreamingDetector] filters the samples as they are pushed instead, carrying the
+    /// previous sample across pushes, so overlapping windows have no seams.
+    pub fn set_pre_emphasis(&mut self, coefficient: Option<T>) {
+        self.pre_emphasis = coefficient;
+    }
+
+    /// Removes a single frequency from the spectrum before looking for peaks, off by default.
+    ///
+    /// Meant for 50 or 60 Hz mains hum: the buckets within the main lobe of the window
+    /// around `freq` are zeroed, which works best with a tapered window, as leakage of
+    /// [WindowFunction::Rectangular] reaches far beyond its main lobe. Combine with
+    /// [Self::set_remove_dc] against a DC bias, and with a minimum frequency of
+    /// [Self::set_freq_range] to ignore everything below a cutoff.
+    /// [Self::spectrum] reports the magnitudes after the notch.
+    ///
+    /// # Errors
+    /// - if `freq` is not between `0` and the Nyquist frequency
+    pub fn set_notch(&mut self, freq: Option<T>) -> Result<(), DetectorCreateError> {
+        if let Some(freq) = freq {
+            if !(freq > T::zero() && freq < float(self.sample_rate as f64 / 2.0)) {
+                return Err(DetectorCreateError::InvalidTargetFreq);
+            }
+        }
+        self.notch = freq;
+        Ok(())
+    }
+
+    /// Subtracts an estimate of the noise floor from the spectrum before looking for peaks,
+    /// off by default.
+    ///
+    /// The floor at every bucket is the median magnitude of the `width` buckets around it,
+    /// so tonal peaks narrower than half of `width` stand out even on top of colored noise,
+    /// which otherwise can outweigh them with its broad hump. A couple of main lobe widths
+    /// works best, e.g. `8` buckets for [WindowFunction::Hann]: wider medians lag behind
+    /// steep noise slopes. [Self::spectrum] reports the magnitudes after the subtraction.
+    pub fn set_noise_floor_width(&mut self, width: Option<usize>) {
+        self.noise_floor_width = width;
+    }
+
+    /// Changes which peak of the spectrum is detected, [StrongestPicker] by default.
+    ///
+    /// Applies to [Self::detect] and the other methods refining a single peak,
+    /// not to [DetectionMethod]s other than [DetectionMethod::StrongestPeak].
+    pub fn set_peak_picker(&mut self, peak_picker: impl PeakPicker<T> + 'static) {
+        self.peak_picker = Arc::new(peak_picker);
+    }
+
+    /// Peaks whose two biggest adjacent buckets sum up to less than `threshold` are
+    /// considered silence, `0.0001` by default.
+    ///
+    /// FFT magnitudes are not normalized, so they grow with the `sample_count`:
+    /// a full-scale sinusoid produces a peak of about `sample_count / 2`.
+    pub fn set_silence_threshold(&mut self, threshold: T) {
+        self.silence_threshold = threshold;
+    }
+
+    /// Same as [Self::set_silence_threshold], but in dBFS of the amplitude of a tone,
+    /// so the threshold does not depend on the `sample_count` and the window.
+    ///
+    /// Tones quieter than `dbfs` are silence, e.g. `-60.0`. The threshold is converted
+    /// with [WindowFunction::amplitude_correction] for the current `sample_count`,
+    /// call this again after [Self::set_sample_count].
+    pub fn set_silence_threshold_dbfs(&mut self, dbfs: T) {
+        let amplitude = float::<T>(10.0).powf(dbfs / float(20.0));
+        self.silence_threshold = amplitude * float(self.sample_count as f64)
+            / float(2.0 * self.window_function.amplitude_correction());
+    }
+
+    /// Peaks less than `db` above the noise floor are considered silence, off by default.
+    ///
+    /// The noise floor is estimated as the median magnitude of the searched buckets of
+    /// every spectrum, so unlike [Self::set_silence_threshold] the threshold follows the
+    /// scale of the signal, e.g. `20.0` for a peak ten times above the noise.
+    /// Both thresholds apply if both are set. After
+    /// [Self::set_noise_floor_width] the floor is mostly gone and nearly every peak passes.
+    pub fn set_min_snr_db(&mut self, db: Option<T>) {
+        self.min_snr_db = db;
+    }
+
+    /// Limits the peak search to buckets between `min_freq` and `max_freq`,
+    /// so that strong components outside of the band are ignored entirely.
+    ///
+    /// [Self::spectrum] still reports all of the buckets.
+    ///
+    /// # Errors
+    /// - if `min_freq >= max_freq`
+    /// - if `min_freq` is above the Nyquist frequency
+    /// - if fewer than 2 buckets fall within the band
+    pub fn set_freq_range(&mut self, min_freq: T, max_freq: T) -> Result<(), DetectorCreateError> {
+        // also rejects NaNs
+        if min_freq.partial_cmp(&max_freq) != Some(Ordering::Less) {
+            return Err(DetectorCreateError::EmptyFreqRange);
         }
+        if min_freq > float(self.sample_rate as f64 / 2.0) {
+            return Err(DetectorCreateError::FreqRangeAboveNyquist);
+        }
+        if self.buckets_within(min_freq, max_freq).len() < 2 {
+            return Err(DetectorCreateError::FreqRangeTooNarrow);
+        }
+        self.freq_range = Some((min_freq, max_freq));
+        Ok(())
+    }
+
+    /// Changes the number of samples expected by [Self::detect], e.g. to trade accuracy
+    /// for latency on the fly.
+    ///
+    /// The FFT plan, window coefficients and buffers are recomputed. Plans are cached,
+    /// so switching back to a sample count used before is cheap.
+    /// Samples are still zero-padded as set by [FreqDetectorBuilder::zero_pad_to],
+    /// unless `sample_count` is at least as big.
+    ///
+    /// # Errors
+    /// - if fewer than 4 samples are passed
+    /// - if fewer than 2 buckets of the new size fall within the frequency range,
+    ///   see [Self::set_freq_range]
+    ///
+    /// The detector is left unchanged on error.
+    pub fn set_sample_count(&mut self, sample_count: usize) -> Result<(), DetectorCreateError> {
         if sample_count < 4 {
             return Err(DetectorCreateError::TooFewSamples);
         }
-        Ok(Self {
-            fft: planner.plan_fft_forward(sample_count),
+        let fft_len = padded_len(sample_count, self.zero_pad_to);
+        let previous_fft = std::mem::replace(&mut self.fft, self.planner.plan_fft_forward(fft_len));
+        if let Some((min_freq, max_freq)) = self.freq_range {
+            if self.buckets_within(min_freq, max_freq).len() < 2 {
+                self.fft = previous_fft;
+                return Err(DetectorCreateError::FreqRangeTooNarrow);
+            }
+        }
 
-            sample_count,
-            sample_rate,
-        })
+        let window = self.window_function.coefficients(sample_count);
+        self.sample_count = sample_count;
+        self.peak_offsets = PeakOffsetTable::new(&window, fft_len);
+        self.min_peak_separation = self
+            .window_function
+            .main_lobe_half_width(sample_count, fft_len);
+        self.window = window.into_iter().map(float).collect();
+        self.scratch = Scratch::new(self.fft.as_ref());
+        self.complex_fft = OnceLock::new();
+        self.inverse_fft = OnceLock::new();
+        Ok(())
+    }
+
+    /// Number of samples expected by [Self::detect]
+    pub fn sample_count(&self) -> usize {
+        self.sample_count
+    }
+
+    /// Sample rate passed to [Self::new]
+    pub fn sample_rate(&self) -> usize {
+        self.sample_rate
     }
 
+    /// Returns `0.0` for silence, see [Self::detect_pitch] to tell it apart.
+    ///
     /// # Errors
     ///
     /// - if `samples.len()` does not match the `sample_count` passed to [Self::new]
-    /// - if there are `NaN`s in the sample slice
-    pub fn detect(&self, samples: &[f32]) -> Result<f32, DetectError> {
-        if samples.len() != self.sample_count {
-            return Err(DetectError::SampleCountMismatch {
-                expected: self.sample_count,
-                passed: samples.len(),
-            });
-        }
-        let mut fft_buf = samples
-            .iter()
-            .copied()
-            .map(|s| Complex { re: s, im: 0.0 })
-            .collect::<Vec<_>>();
+    /// - if there are `NaN`s or infinities in the sample slice
+    /// - if the searched frequency range holds fewer than 2 buckets
+    pub fn detect(&self, samples: &[T]) -> Result<T, DetectError> {
+        Ok(self.detect_full(samples)?.frequency)
+    }
+
+    /// Same as [Self::detect], but reports everything known about the peak
+    /// from a single FFT, see [DetectionResult].
+    ///
+    /// # Errors
+    ///
+    /// Same as [Self::detect]
+    pub fn detect_full(&self, samples: &[T]) -> Result<DetectionResult<T>, DetectError> {
+        let magnitudes = self.magnitudes(samples.iter().copied())?;
+        self.describe_peak(&magnitudes, &mut vec![])
+    }
 
-        self.fft.process(&mut fft_buf);
+    /// [DetectionResult] of the strongest peak of `magnitudes`
+    fn describe_peak(
+        &self,
+        magnitudes: &[T],
+        median_buffer: &mut Vec<T>,
+    ) -> Result<DetectionResult<T>, DetectError> {
+        let left = self.peak_window(magnitudes, median_buffer)?;
+        let refined = self.refine_peak(magnitudes, left, median_buffer);
+        let bucket = if magnitudes[left + 1] > magnitudes[left] {
+            left + 1
+        } else {
+            left
+        };
+        let nyquist_bucket = magnitudes.len() - 1;
+        Ok(DetectionResult {
+            frequency: refined.unwrap_or_else(T::zero),
+            magnitude: magnitudes[left] + magnitudes[left + 1],
+            confidence: self.confidence(magnitudes, left),
+            is_silent: refined.is_none(),
+            bucket,
+            near_nyquist: refined.is_some() && bucket * 20 >= nyquist_bucket * 19,
+        })
+    }
 
-        let antialised_power_values = fft_buf
-            .windows(2)
-            // only interested in positive frequencies
-            .take(self.sample_count / 2)
-            .map(|w| [w[0].abs(), w[1].abs()])
-            .enumerate()
-            .collect::<Vec<_>>();
+    /// Same as [Self::detect], but tells silence apart from a low-frequency pitch
+    ///
+    /// # Errors
+    ///
+    /// Same as [Self::detect]
+    pub fn detect_pitch(&self, samples: &[T]) -> Result<Detection<T>, DetectError> {
+        let magnitudes = self.magnitudes(samples.iter().copied())?;
+        let mut median_buffer = vec![];
+        let left = self.peak_window(&magnitudes, &mut median_buffer)?;
+        Ok(self
+            .refine_peak(&magnitudes, left, &mut median_buffer)
+            .map_or(Detection::Silent, Detection::Pitch))
+    }
 
-        let peak_window = antialised_power_values
-            .iter()
-            .copied()
-            .max_by(|c1, c2| {
-                c1.1.iter()
-                    .sum::<f32>()
-                    .total_cmp(&c2.1.iter().sum::<f32>())
-            })
-            .expect("to have at least 1 positive frequency");
+    /// How many cents the detected pitch is above (positive) or below (negative) `target`,
+    /// `None` for silence. See [cents_off].
+    ///
+    /// # Errors
+    ///
+    /// Same as [Self::detect]
+    pub fn detect_cents(&self, samples: &[T], target: T) -> Result<Option<T>, DetectError> {
+        Ok(match self.detect_pitch(samples)? {
+            Detection::Pitch(freq) if freq > T::zero() && target > T::zero() => {
+                Some(float::<T>(1200.0) * (self.output_to_freq(freq) / target).log2())
+            }
+            _ => None,
+        })
+    }
 
-        if peak_window.1.iter().sum::<f32>() < 0.0001 {
-            return Ok(0.0);
-        }
+    /// Same as [Self::detect], but reuses buffers stored in the detector,
+    /// so repeated detections do not allocate.
+    ///
+    /// # Errors
+    ///
+    /// Same as [Self::detect]
+    pub fn detect_mut(&mut self, samples: &[T]) -> Result<T, DetectError> {
+        let mut scratch = std::mem::take(&mut self.scratch);
+        let res = self
+            .fill_magnitudes(samples.iter().copied(), &mut scratch)
+            .and_then(|()| self.picked_peak(&scratch.magnitudes, &mut scratch.median_buffer));
+        self.scratch = scratch;
+        res
+    }
 
-        // take weighted average of the two biggest
-        // not sure what is the math behind why this works, but it does
-        let res = (self.fft_bucket_to_freq(peak_window.0) * peak_window.1[0]
-            + self.fft_bucket_to_freq(peak_window.0 + 1) * peak_window.1[1])
-            / peak_window.1.iter().sum::<f32>();
-        if res.is_nan() {
-            Err(DetectError::NansFound)
-        } else {
-            Ok(res)
+    /// Same as [Self::detect_pitch] of samples the pre-emphasis filter was already
+    /// applied to, reusing the buffers of `scratch`
+    pub(crate) fn detect_pitch_reusing(
+        &self,
+        samples: &[T],
+        scratch: &mut Scratch<T>,
+    ) -> Result<Detection<T>, DetectError> {
+        self.fill_magnitudes_with(samples.iter().copied(), scratch, None)?;
+        let magnitudes = &scratch.magnitudes;
+        let left = self.peak_window(magnitudes, &mut scratch.median_buffer)?;
+        Ok(self
+            .refine_peak(magnitudes, left, &mut scratch.median_buffer)
+            .map_or(Detection::Silent, Detection::Pitch))
+    }
+
+    /// Runs [Self::detect_mut] once on silence, so that every buffer it reuses is allocated
+    /// and touched up front.
+    ///
+    /// The FFT itself is fully planned by the constructors. Constructing the detector
+    /// off the realtime thread and calling this before handing it over keeps
+    /// [Self::detect_mut] in an audio callback free of allocations, the other detection
+    /// methods allocate their buffers on every call. Needed again after [Self::clone]
+    /// and [Self::set_sample_count].
+    pub fn warm_up(&mut self) {
+        let silence = vec![T::zero(); self.sample_count];
+        // silence passes every check, the result is of no interest
+        let _ = self.detect_mut(&silence);
+    }
+
+    /// Same as [Self::detect], but picks the frequency according to `method`
+    ///
+    /// # Errors
+    ///
+    /// Same as [Self::detect]
+    pub fn detect_with_method(
+        &self,
+        samples: &[T],
+        method: DetectionMethod,
+    ) -> Result<T, DetectError> {
+        if method == DetectionMethod::Yin {
+            let yin = YinDetector::new(self.sample_rate, self.sample_count, float(YIN_THRESHOLD))
+                .expect("detector to be validated already");
+            return Ok(yin.detect(samples)?.map_or_else(T::zero, |freq| {
+                self.bucket_to_output(freq / self.bucket_resolution())
+            }));
+        }
+        let magnitudes = self.magnitudes(samples.iter().copied())?;
+        let mut median_buffer = vec![];
+        match method {
+            DetectionMethod::Yin => unreachable!("handled above"),
+            DetectionMethod::Cepstrum => {
+                let left = self.peak_window(&magnitudes, &mut median_buffer)?;
+                if self
+                    .refine_peak(&magnitudes, left, &mut median_buffer)
+                    .is_none()
+                {
+                    return Ok(T::zero());
+                }
+                let inverse_fft = self
+                    .inverse_fft
+                    .get_or_init(|| RealFftPlanner::new().plan_fft_inverse(self.fft.len()));
+                let (min_freq, max_freq) = self
+                    .freq_range
+                    .unwrap_or((float(CEPSTRUM_FREQ_RANGE.0), float(CEPSTRUM_FREQ_RANGE.1)));
+                let sample_rate = float::<T>(self.sample_rate as f64);
+                let quefrencies = (sample_rate / max_freq).ceil().to_usize().unwrap_or(0)
+                    ..(sample_rate / min_freq)
+                        .floor()
+                        .to_usize()
+                        .map_or(usize::MAX, |period| period.saturating_add(1));
+                let period =
+                    cepstrum::cepstrum_peak(&magnitudes, inverse_fft.as_ref(), quefrencies);
+                Ok(period.map_or_else(T::zero, |period| {
+                    self.bucket_to_output(sample_rate / period / self.bucket_resolution())
+                }))
+            }
+            DetectionMethod::StrongestPeak => self.picked_peak(&magnitudes, &mut median_buffer),
+            DetectionMethod::HarmonicProductSpectrum { harmonics } => {
+                let bucket = harmonic::harmonic_product_spectrum(
+                    &magnitudes,
+                    harmonics,
+                    self.search_buckets(),
+                );
+                Ok(self
+                    .refine_peak_around(&magnitudes, bucket, &mut median_buffer)
+                    .unwrap_or_else(T::zero))
+            }
+            DetectionMethod::SubharmonicSummation { harmonics } => {
+                let (bucket, harmonic) =
+                    harmonic::subharmonic_summation(&magnitudes, harmonics, self.search_buckets());
+                // the fundamental itself may be too weak to refine, every output unit
+                // is proportional to the frequency
+                Ok(self
+                    .refine_peak_around(&magnitudes, bucket, &mut median_buffer)
+                    .map_or_else(T::zero, |freq| freq / float(harmonic as f64)))
+            }
+            DetectionMethod::HarmonicSpacing { peaks } => {
+                let buckets = self.peak_buckets(&magnitudes, peaks);
+                let Some(&strongest) = buckets.first() else {
+                    return Ok(T::zero());
+                };
+                let min_magnitude = magnitudes[strongest] * float(MIN_PEAK_SHARE);
+                let freqs = buckets
+                    .into_iter()
+                    .filter(|&bucket| magnitudes[bucket] >= min_magnitude)
+                    .filter_map(|bucket| {
+                        self.refine_peak_around(&magnitudes, bucket, &mut median_buffer)
+                    })
+                    .collect::<Vec<_>>();
+                Ok(harmonic::harmonic_spacing(&freqs)
+                    .or(freqs.first().copied())
+                    .unwrap_or_else(T::zero))
+            }
         }
     }
 
-    fn fft_bucket_to_freq(&self, bucket: usize) -> f32 {
-        bucket as f32 * self.sample_rate as f32 / self.sample_count as f32
+    /// Same as [Self::detect], but also returns how much the peak stands out, as
+    /// `(frequency, confidence)`.
+    ///
+    /// Confidence is the share of the spectral energy contained in the main lobe of the peak,
+    /// from `0.0` to `1.0`. It is close to `1.0` for a clean tone and close to `0.0` for
+    /// noise or silence, so it can be thresholded to reject unreliable detections.
+    ///
+    /// # Errors
+    ///
+    /// Same as [Self::detect]
+    pub fn detect_with_confidence(&self, samples: &[T]) -> Result<(T, T), DetectError> {
+        let result = self.detect_full(samples)?;
+        Ok((result.frequency, result.confidence))
     }
-}
 
-#[derive(Error, Debug)]
-pub enum DetectError {
-    #[error("Invalid sample count passed (expected {expected}, passed {passed})")]
-    SampleCountMismatch { expected: usize, passed: usize },
-    #[error("NaNs in the samples")]
-    NansFound,
-}
+    /// Same as [Self::detect], but also returns the magnitude of the peak
+    /// as `(frequency, peak_magnitude)`.
+    ///
+    /// The magnitude is the sum of the two adjacent buckets the peak is refined from,
+    /// the same value compared against [Self::set_silence_threshold]. Unlike the RMS level
+    /// of [Self::detect_with_level], it only includes the dominant partial.
+    ///
+    /// # Errors
+    ///
+    /// Same as [Self::detect]
+    pub fn detect_with_magnitude(&self, samples: &[T]) -> Result<(T, T), DetectError> {
+        let result = self.detect_full(samples)?;
+        Ok((result.frequency, result.magnitude))
+    }
 
-#[derive(Error, Debug)]
-pub enum DetectorCreateError {
-    #[error("Detector does not support sample rate < 1 sample per second")]
-    SampleRateTooLow,
-    #[error("Needs at least 4 samples for detection")]
-    TooFewSamples,
-}
+    /// Same as [Self::detect], but also returns the amplitude of the peak in dBFS
+    /// as `(frequency, peak_dbfs)`, where `0.0` is a full-scale sine.
+    ///
+    /// The strongest bucket of the peak is normalized to the amplitude of a tone as
+    /// `2 * magnitude * amplitude_correction / sample_count`, see
+    /// [WindowFunction::amplitude_correction], so the value does not depend on
+    /// `sample_count` or the window. Zero padding adds no energy, so it does not change
+    /// the value either. Off-center tones read up to 1.4 dB low with [WindowFunction::Hann]
+    /// and are exact with [WindowFunction::FlatTop]. Silence is clamped to [DBFS_FLOOR].
+    ///
+    /// # Errors
+    ///
+    /// Same as [Self::detect]
+    pub fn detect_peak_db(&self, samples: &[T]) -> Result<(T, T), DetectError> {
+        let magnitudes = self.magnitudes(samples.iter().copied())?;
+        let mut median_buffer = vec![];
+        let left = self.peak_window(&magnitudes, &mut median_buffer)?;
+        let freq = self
+            .refine_peak(&magnitudes, left, &mut median_buffer)
+            .unwrap_or_else(T::zero);
+        let amplitude = float::<T>(2.0 * self.window_function.amplitude_correction())
+            * magnitudes[left].max(magnitudes[left + 1])
+            / float(self.sample_count as f64);
+        Ok((freq, amplitude_to_dbfs(amplitude)))
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::FreqDetector;
+    /// Same as [Self::detect], but also returns the root-mean-square level of the samples
+    /// as `(frequency, rms_level)`.
+    ///
+    /// Use [amplitude_to_dbfs] to get the level in dBFS.
+    ///
+    /// # Errors
+    ///
+    /// Same as [Self::detect]
+    pub fn detect_with_level(&self, samples: &[T]) -> Result<(T, T), DetectError> {
+        let freq = self.detect(samples)?;
+        let mean_square =
+            samples.iter().fold(T::zero(), |sum, s| sum + *s * *s) / float(samples.len() as f64);
+        Ok((freq, mean_square.sqrt()))
+    }
 
-    #[test]
-    fn freq_detector_smoke_test() {
-        use std::f32::consts::TAU;
-        let sample_count = 4096 * 2;
-        let freq_detector = FreqDetector::new(44100, sample_count).unwrap();
+    /// Detects the frequency of every consecutive, non-overlapping chunk of `sample_count`
+    /// samples. A trailing chunk shorter than `sample_count` is ignored.
+    ///
+    /// The same buffers are reused for all of the chunks.
+    pub fn detect_batch(&self, samples: &[T]) -> Vec<Result<T, DetectError>> {
+        let mut scratch = Scratch::new(self.fft.as_ref());
+        samples
+            .chunks_exact(self.sample_count)
+            .map(|chunk| {
+                self.fill_magnitudes(chunk.iter().copied(), &mut scratch)?;
+                self.picked_peak(&scratch.magnitudes, &mut scratch.median_buffer)
+            })
+            .collect()
+    }
 
-        for freq in [10, 20, 30, 100, 1000, 2000] {
-            let sin_samples = (0..sample_count)
-                .map(|i| {
-                    (i as f32 / 44100.0 * freq as f32 * TAU).sin()
-                        // noise
-                        + 0.9 * (i as f32 / 44100.0 * 101.0 * TAU).sin()
-                        + 0.9 * (i as f32 / 44100.0 * 120.0 * TAU).sin()
-                })
-                .collect::<Vec<_>>();
+    /// Detects the frequency of every channel of a buffer of `sample_count` interleaved
+    /// frames, as delivered by most audio APIs. Returns one frequency per channel.
+    ///
+    /// The same buffers are reused for all of the channels.
+    ///
+    /// # Errors
+    ///
+    /// - if `channels` is `0`
+    /// - if `interleaved.len()` is not a multiple of `channels`
+    /// - if `interleaved.len()` is not `sample_count * channels`
+    /// - the first error of [Self::detect] for any of the channels
+    pub fn detect_channels(
+        &self,
+        interleaved: &[T],
+        channels: usize,
+    ) -> Result<Vec<T>, DetectError> {
+        self.check_interleaved(interleaved, channels)?;
+        let mut scratch = Scratch::new(self.fft.as_ref());
+        (0..channels)
+            .map(|channel| {
+                let samples = interleaved.iter().skip(channel).step_by(channels).copied();
+                self.fill_magnitudes(samples, &mut scratch)?;
+                self.picked_peak(&scratch.magnitudes, &mut scratch.median_buffer)
+            })
+            .collect()
+    }
 
-            let detected_freq = freq_detector.detect(&sin_samples).unwrap();
-            dbg!(detected_freq, freq);
-            assert!(
-                (detected_freq - freq as f32).abs() < 0.5,
-                "detected {detected_freq} expected {freq}"
-            );
+    /// Detects the frequency in a buffer of `sample_count` interleaved frames of `channels`
+    /// samples each, mixed down or picked according to `mode`.
+    ///
+    /// Returns a single frequency for [ChannelMode::Mix] and [ChannelMode::Select],
+    /// one per channel for [ChannelMode::PerChannel].
+    ///
+    /// # Errors
+    ///
+    /// - if the selected channel is not below `channels`
+    /// - same as [Self::detect_channels]
+    pub fn detect_interleaved(
+        &self,
+        interleaved: &[T],
+        channels: usize,
+        mode: ChannelMode,
+    ) -> Result<Vec<T>, DetectError> {
+        self.check_interleaved(interleaved, channels)?;
+        let magnitudes = match mode {
+            ChannelMode::Mix => {
+                let scale = float::<T>(channels as f64).recip();
+                self.magnitudes(interleaved.chunks_exact(channels).map(|frame| {
+                    frame.iter().fold(T::zero(), |sum, sample| sum + *sample) * scale
+                }))?
+            }
+            ChannelMode::Select(channel) if channel < channels => {
+                self.magnitudes(interleaved.iter().skip(channel).step_by(channels).copied())?
+            }
+            ChannelMode::Select(channel) => {
+                return Err(DetectError::NoSuchChannel { channel, channels })
+            }
+            ChannelMode::PerChannel => return self.detect_channels(interleaved, channels),
+        };
+        Ok(vec![self.picked_peak(&magnitudes, &mut vec![])?])
+    }
+
+    /// `interleaved` holds `sample_count` whole frames of `channels` samples
+    fn check_interleaved(&self, interleaved: &[T], channels: usize) -> Result<(), DetectError> {
+        if channels == 0 {
+            return Err(DetectError::NoChannels);
+        }
+        if !interleaved.len().is_multiple_of(channels) {
+            return Err(DetectError::IncompleteFrame {
+                passed: interleaved.len(),
+                channels,
+            });
+        }
+        if interleaved.len() != self.sample_count * channels {
+            return Err(DetectError::SampleCountMismatch {
+                expected: self.sample_count * channels,
+                passed: interleaved.len(),
+            });
         }
+        Ok(())
+    }
+
+    /// Lazily detects the frequency of windows of `sample_count` samples taken every
+    /// `hop_size` samples, so overlapping windows can be processed in an iterator chain.
+    /// Trailing samples that do not fill a whole window are ignored.
+    ///
+    /// Nothing is computed until the iterator is advanced, and the same buffers are
+    /// reused for all of the windows.
+    ///
+    /// # Panics
+    ///
+    /// - if `hop_size` is `0`
+    pub fn detect_windows<'a>(
+        &'a self,
+        samples: &'a [T],
+        hop_size: usize,
+    ) -> impl Iterator<Item = Result<T, DetectError>> + 'a {
+        assert!(hop_size > 0, "hop size must be at least 1 sample");
+        let mut scratch = Scratch::new(self.fft.as_ref());
+        samples
+            .windows(self.sample_count)
+            .step_by(hop_size)
+            .map(move |window| {
+                self.fill_magnitudes(window.iter().copied(), &mut scratch)?;
+                self.picked_peak(&scratch.magnitudes, &mut scratch.median_buffer)
+            })
+    }
+
+    /// Detects the frequency of every window of `sample_count` samples taken every
+    /// `hop_size` samples, for offline analysis of a whole recording.
+    /// Trailing samples that do not fill a whole window are ignored.
+    ///
+    /// # Errors
+    ///
+    /// - if there are `NaN`s or infinities in the sample slice
+    /// - if the searched frequency range holds fewer than 2 buckets
+    ///
+    /// # Panics
+    ///
+    /// - if `hop_size` is `0`
+    pub fn detect_all(
+        &self,
+        samples: &[T],
+        hop_size: usize,
+    ) -> Result<Vec<TimedDetection<T>>, DetectError> {
+        assert!(hop_size > 0, "hop size must be at least 1 sample");
+        let mut scratch = Scratch::new(self.fft.as_ref());
+        samples
+            .windows(self.sample_count)
+            .step_by(hop_size)
+            .enumerate()
+            .map(|(index, window)| {
+                self.fill_magnitudes(window.iter().copied(), &mut scratch)?;
+                self.timed_detection(
+                    index * hop_size,
+                    &scratch.magnitudes,
+                    &mut scratch.median_buffer,
+                )
+            })
+            .collect()
+    }
+
+    /// [TimedDetection] of the window starting at `sample`
+    fn timed_detection(
+        &self,
+        sample: usize,
+        magnitudes: &[T],
+        median_buffer: &mut Vec<T>,
+    ) -> Result<TimedDetection<T>, DetectError> {
+        let peak = self.describe_peak(magnitudes, median_buffer)?;
+        Ok(TimedDetection {
+            sample,
+            time: float::<T>(sample as f64) / float(self.sample_rate as f64),
+            frequency: peak.frequency,
+            confidence: peak.confidence,
+        })
+    }
+
+    /// Vibrato of the pitch detected over windows taken every `hop_size` samples,
+    /// see [vibrato].
+    ///
+    /// `Ok(None)` if any of the windows is silent.
+    ///
+    /// # Errors
+    ///
+    /// - if there are `NaN`s or infinities in the sample slice
+    ///
+    /// # Panics
+    ///
+    /// - if `hop_size` is `0`
+    pub fn detect_vibrato(
+        &self,
+        samples: &[T],
+        hop_size: usize,
+    ) -> Result<Option<Vibrato<T>>, DetectError> {
+        let pitches = self
+            .detect_windows(samples, hop_size)
+            .collect::<Result<Vec<_>, _>>()?;
+        let frame_rate = float::<T>(self.sample_rate as f64 / hop_size as f64);
+        Ok(vibrato(&pitches, frame_rate))
+    }
+
+    /// Same as [Self::detect_batch], but detects the chunks in parallel.
+    ///
+    /// Every worker thread gets its own buffers, the FFT plan is shared.
+    #[cfg(feature = "rayon")]
+    pub fn detect_batch_par(&self, samples: &[T]) -> Vec<Result<T, DetectError>> {
+        use rayon::prelude::*;
+
+        samples
+            .par_chunks_exact(self.sample_count)
+            .map_init(
+                || Scratch::new(self.fft.as_ref()),
+                |scratch, chunk| {
+                    self.fill_magnitudes(chunk.iter().copied(), scratch)?;
+                    self.picked_peak(&scratch.magnitudes, &mut scratch.median_buffer)
+                },
+            )
+            .collect()
+    }
+
+    /// Same as [Self::detect_all], but detects the windows in parallel.
+    ///
+    /// Every worker thread gets its own buffers, the FFT plan is shared.
+    ///
+    /// # Errors
+    ///
+    /// Same as [Self::detect_all]
+    ///
+    /// # Panics
+    ///
+    /// - if `hop_size` is `0`
+    #[cfg(feature = "rayon")]
+    pub fn detect_all_par(
+        &self,
+        samples: &[T],
+        hop_size: usize,
+    ) -> Result<Vec<TimedDetection<T>>, DetectError> {
+        use rayon::prelude::*;
+
+        assert!(hop_size > 0, "hop size must be at least 1 sample");
+        samples
+            .par_windows(self.sample_count)
+            .step_by(hop_size)
+            .enumerate()
+            .map_init(
+                || Scratch::new(self.fft.as_ref()),
+                |scratch, (index, window)| {
+                    self.fill_magnitudes(window.iter().copied(), scratch)?;
+                    self.timed_detection(
+                        index * hop_size,
+                        &scratch.magnitudes,
+                        &mut scratch.median_buffer,
+                    )
+                },
+            )
+            .collect()
+    }
+
+    /// Same as [Self::detect] for complex samples, such as I/Q data from a software-defined radio.
+    ///
+    /// The negative frequencies of a complex signal are distinct from the positive ones,
+    /// so the full spectrum is searched and the result is between minus and plus
+    /// the Nyquist frequency. [Self::set_notch] removes its frequency on both sides
+    /// of 0 Hz, the noise floor of [Self::set_noise_floor_width] and [Self::set_min_snr_db]
+    /// is taken from the whole spectrum. The frequency range of [Self::set_freq_range]
+    /// and the [PeakPicker] are ignored, the strongest peak is always reported.
+    /// `Complex` is available as `freq_det::realfft::num_complex::Complex`.
+    ///
+    /// # Errors
+    ///
+    /// - if `samples.len()` does not match the `sample_count` passed to [Self::new]
+    /// - if there are `NaN`s or infinities in the sample slice
+    pub fn detect_complex(&self, samples: &[Complex<T>]) -> Result<T, DetectError> {
+        if samples.len() != self.sample_count {
+            return Err(DetectError::SampleCountMismatch {
+                expected: self.sample_count,
+                passed: samples.len(),
+            });
+        }
+        for sample in samples {
+            check_finite(&[sample.re, sample.im])?;
+        }
+        let fft = self
+            .complex_fft
+            .get_or_init(|| FftPlanner::new().plan_fft_forward(self.fft.len()));
+
+        let dc_offset = if self.remove_dc {
+            samples
+                .iter()
+                .fold(Complex::new(T::zero(), T::zero()), |sum, s| sum + s)
+                / float::<T>(self.sample_count as f64)
+        } else {
+            Complex::new(T::zero(), T::zero())
+        };
+        let mut buffer = Vec::with_capacity(fft.len());
+        let mut previous = Complex::new(T::zero(), T::zero());
+        for (s, w) in samples.iter().zip(&self.window) {
+            let sample = s - dc_offset;
+            let filtered = match self.pre_emphasis {
+                Some(coefficient) => sample - previous * coefficient,
+                None => sample,
+            };
+            previous = sample;
+            buffer.push(filtered * *w);
+        }
+        buffer.resize(fft.len(), Complex::new(T::zero(), T::zero()));
+        fft.process(&mut buffer);
+
+        // negative frequencies first, so that the peak search runs from -Nyquist to +Nyquist
+        let negative_buckets = fft.len() / 2;
+        let mut scratch = Scratch {
+            magnitudes: buffer[fft.len() - negative_buckets..]
+                .iter()
+                .chain(&buffer[..fft.len() - negative_buckets])
+                .map(|c| c.norm())
+                .collect(),
+            ..Scratch::default()
+        };
+        if let Some(notched) = self.notched_buckets() {
+            let magnitudes = &mut scratch.magnitudes;
+            for bucket in notched {
+                magnitudes[negative_buckets - bucket] = T::zero();
+                if let Some(positive) = magnitudes.get_mut(negative_buckets + bucket) {
+                    *positive = T::zero();
+                }
+            }
+        }
+        if let Some(width) = self.noise_floor_width {
+            subtract_noise_floor(&mut scratch, width);
+        }
+        let magnitudes = scratch.magnitudes;
+        let (left, _) = magnitudes
+            .windows(2)
+            .enumerate()
+            .max_by(|c1, c2| compare(c1.1[0] + c1.1[1], c2.1[0] + c2.1[1]))
+            .ok_or(DetectError::EmptySpectrum)?;
+        // the whole spectrum is searched, and the buckets are not in positive order
+        Ok(self
+            .refine_peak_within(&magnitudes, left, 0..magnitudes.len(), &mut vec![])
+            .map_or(T::zero(), |freq| {
+                freq - self.bucket_to_output(float(negative_buckets as f64))
+            }))
+    }
+
+    /// Same as [Self::detect] for 16-bit PCM, normalized to `[-1.0, 1.0]` on the fly
+    /// without allocating an intermediate float buffer.
+    ///
+    /// # Errors
+    ///
+    /// - if `samples.len()` does not match the `sample_count` passed to [Self::new]
+    pub fn detect_i16(&self, samples: &[i16]) -> Result<T, DetectError> {
+        let scale = float::<T>(-(i16::MIN as f64));
+        let magnitudes = self.magnitudes(samples.iter().map(|s| float::<T>(*s as f64) / scale))?;
+        self.picked_peak(&magnitudes, &mut vec![])
+    }
+
+    /// Same as [Self::detect_i16] for 32-bit PCM
+    ///
+    /// # Errors
+    ///
+    /// - if `samples.len()` does not match the `sample_count` passed to [Self::new]
+    pub fn detect_i32(&self, samples: &[i32]) -> Result<T, DetectError> {
+        let scale = float::<T>(-(i32::MIN as f64));
+        let magnitudes = self.magnitudes(samples.iter().map(|s| float::<T>(*s as f64) / scale))?;
+        self.picked_peak(&magnitudes, &mut vec![])
+    }
+
+    /// Same as [Self::detect_i16] for unsigned 8-bit PCM, as in 8-bit WAV files,
+    /// where silence is `128`
+    ///
+    /// # Errors
+    ///
+    /// - if `samples.len()` does not match the `sample_count` passed to [Self::new]
+    pub fn detect_u8(&self, samples: &[u8]) -> Result<T, DetectError> {
+        let magnitudes = self.magnitudes(
+            samples
+                .iter()
+                .map(|s| float::<T>((*s as f64 - 128.0) / 128.0)),
+        )?;
+        self.picked_peak(&magnitudes, &mut vec![])
+    }
+
+    /// Detects up to `n` strongest spectral peaks, sorted by descending magnitude.
+    ///
+    /// Only local maxima of the spectrum are considered, and peaks closer to a stronger one
+    /// than the main lobe of the window are skipped, so a single tone is reported once.
+    /// Each peak is refined the same way as in [Self::detect].
+    /// Fewer than `n` frequencies are returned if there are not enough peaks above silence.
+    ///
+    /// # Errors
+    ///
+    /// Same as [Self::detect]
+    pub fn detect_n(&self, samples: &[T], n: usize) -> Result<Vec<T>, DetectError> {
+        let magnitudes = self.magnitudes(samples.iter().copied())?;
+        let mut median_buffer = vec![];
+        Ok(self
+            .peak_buckets(&magnitudes, n)
+            .into_iter()
+            .filter_map(|bucket| self.refine_peak_around(&magnitudes, bucket, &mut median_buffer))
+            .collect())
+    }
+
+    /// Notes sounding at the same time, lowest first.
+    ///
+    /// Peaks weaker than a tenth of the strongest one are ignored, and so are overtones:
+    /// peaks close to an integer multiple of a stronger peak. That also drops notes an octave
+    /// or a twelfth above another note of the chord. Use a tapered window such as
+    /// [WindowFunction::Hann], the side lobes of [WindowFunction::Rectangular] are strong
+    /// enough to be mistaken for notes.
+    ///
+    /// # Errors
+    ///
+    /// Same as [Self::detect]
+    pub fn detect_chord(&self, samples: &[T]) -> Result<Vec<Note>, DetectError> {
+        const MAX_PEAKS: usize = 16;
+
+        let magnitudes = self.magnitudes(samples.iter().copied())?;
+        let buckets = self.peak_buckets(&magnitudes, MAX_PEAKS);
+        let Some(&strongest) = buckets.first() else {
+            return Ok(vec![]);
+        };
+        let min_magnitude = magnitudes[strongest] * float(MIN_PEAK_SHARE);
+
+        let mut fundamentals: Vec<T> = vec![];
+        let mut median_buffer = vec![];
+        for bucket in buckets {
+            if magnitudes[bucket] < min_magnitude {
+                break;
+            }
+            let Some(freq) = self.refine_peak_around(&magnitudes, bucket, &mut median_buffer)
+            else {
+                continue;
+            };
+            if !fundamentals.iter().any(|f| harmonic::is_overtone(freq, *f)) {
+                fundamentals.push(freq);
+            }
+        }
+        fundamentals.sort_by(|a, b| compare(*a, *b));
+
+        let mut notes: Vec<Note> = vec![];
+        for note in fundamentals
+            .into_iter()
+            .filter_map(|freq| freq_to_note(self.output_to_freq(freq).to_f32()?))
+        {
+            if !notes
+                .iter()
+                .any(|n| (n.name, n.octave) == (note.name, note.octave))
+            {
+                notes.push(note);
+            }
+        }
+        Ok(notes)
+    }
+
+    /// Buckets of up to `n` strongest local maxima, see [Self::detect_n]
+    fn peak_buckets(&self, magnitudes: &[T], n: usize) -> Vec<usize> {
+        let search_buckets = self.search_buckets();
+        let mut local_maxima = (search_buckets.start.max(1)
+            ..search_buckets.end.min(magnitudes.len() - 1))
+            .filter(|&b| magnitudes[b] >= magnitudes[b - 1] && magnitudes[b] > magnitudes[b + 1])
+            .collect::<Vec<_>>();
+        local_maxima.sort_by(|b1, b2| compare(magnitudes[*b2], magnitudes[*b1]));
+
+        let mut peaks: Vec<usize> = vec![];
+        for bucket in local_maxima {
+            if peaks.len() == n {
+                break;
+            }
+            if peaks
+                .iter()
+                .all(|p| p.abs_diff(bucket) > self.min_peak_separation)
+            {
+                peaks.push(bucket);
+            }
+        }
+        peaks
+    }
+
+    /// Magnitude of every positive-frequency bucket, from 0 Hz up to and including Nyquist,
+    /// as `(frequency_hz, magnitude)` pairs.
+    ///
+    /// # Errors
+    ///
+    /// Same as [Self::detect]
+    pub fn spectrum(&self, samples: &[T]) -> Result<Vec<(T, T)>, DetectError> {
+        Ok(self
+            .magnitudes(samples.iter().copied())?
+            .into_iter()
+            .enumerate()
+            .map(|(bucket, magnitude)| (self.fft_bucket_to_freq(float(bucket as f64)), magnitude))
+            .collect())
+    }
+
+    /// Magnitude spectra of frames of `sample_count` samples taken every `hop_size` samples,
+    /// one column of `fft_len / 2 + 1` magnitudes per frame, like in [Self::spectrum].
+    /// `fft_len` is `sample_count` unless the samples are zero-padded.
+    ///
+    /// The window function is applied to every frame. Trailing samples that do not fill
+    /// a whole frame are ignored. See [Self::spectrogram_full] for the axes and dBFS.
+    ///
+    /// # Errors
+    ///
+    /// - if there are `NaN`s or infinities in the sample slice
+    ///
+    /// # Panics
+    ///
+    /// - if `hop_size` is `0`
+    pub fn spectrogram(&self, samples: &[T], hop_size: usize) -> Result<Vec<Vec<T>>, DetectError> {
+        assert!(hop_size > 0, "hop size must be at least 1 sample");
+        let mut scratch = Scratch::new(self.fft.as_ref());
+        samples
+            .windows(self.sample_count)
+            .step_by(hop_size)
+            .map(|frame| {
+                self.fill_magnitudes(frame.iter().copied(), &mut scratch)?;
+                Ok(scratch.magnitudes.clone())
+            })
+            .collect()
+    }
+
+    /// Frequency of the peak chosen by the peak picker, `0.0` for silence
+    fn picked_peak(&self, magnitudes: &[T], median_buffer: &mut Vec<T>) -> Result<T, DetectError> {
+        Ok(self
+            .refine_peak(
+                magnitudes,
+                self.peak_window(magnitudes, median_buffer)?,
+                median_buffer,
+            )
+            .unwrap_or_else(T::zero))
+    }
+
+    /// Left bucket of the two adjacent buckets chosen by the peak picker,
+    /// by default the ones with the biggest total magnitude
+    fn peak_window(
+        &self,
+        magnitudes: &[T],
+        median_buffer: &mut Vec<T>,
+    ) -> Result<usize, DetectError> {
+        let search_buckets = self.search_buckets();
+        let searched = magnitudes
+            .get(search_buckets.clone())
+            .filter(|searched| searched.len() >= 2)
+            .ok_or(DetectError::EmptySpectrum)?;
+        let left = self
+            .peak_picker
+            .pick_with_buffer(searched, median_buffer)
+            .min(searched.len() - 2);
+        Ok(left + search_buckets.start)
+    }
+
+    /// Buckets considered when searching for peaks
+    fn search_buckets(&self) -> Range<usize> {
+        match self.freq_range {
+            Some((min_freq, max_freq)) => self.buckets_within(min_freq, max_freq),
+            None => 0..self.fft.complex_len(),
+        }
+    }
+
+    fn buckets_within(&self, min_freq: T, max_freq: T) -> Range<usize> {
+        let bucket_width = self.bucket_resolution();
+        let positive_buckets = self.fft.complex_len();
+        let from = (min_freq / bucket_width).ceil().to_usize().unwrap_or(0);
+        let to = (max_freq / bucket_width)
+            .floor()
+            .to_usize()
+            .map_or(0, |last| last + 1);
+        from.min(positive_buckets)..to.min(positive_buckets)
+    }
+
+    /// Share of the spectral energy in the main lobe around the window starting at `left`
+    fn confidence(&self, magnitudes: &[T], left: usize) -> T {
+        let energy = |magnitudes: &[T]| {
+            magnitudes
+                .iter()
+                .fold(T::zero(), |energy, m| energy + *m * *m)
+        };
+        let total = energy(magnitudes);
+        if total <= T::zero() {
+            return T::zero();
+        }
+        let main_lobe = (left + 1).saturating_sub(self.min_peak_separation)
+            ..(left + 1 + self.min_peak_separation).min(magnitudes.len());
+        energy(&magnitudes[main_lobe]) / total
+    }
+
+    fn magnitudes(&self, samples: impl ExactSizeIterator<Item = T>) -> Result<Vec<T>, DetectError> {
+        let mut scratch = Scratch::default();
+        self.fill_magnitudes(samples, &mut scratch)?;
+        Ok(scratch.magnitudes)
+    }
+
+    /// Puts the magnitudes of the positive frequencies into `scratch.magnitudes`
+    fn fill_magnitudes(
+        &self,
+        samples: impl ExactSizeIterator<Item = T>,
+        scratch: &mut Scratch<T>,
+    ) -> Result<(), DetectError> {
+        self.fill_magnitudes_with(samples, scratch, self.pre_emphasis)
+    }
+
+    /// Same as [Self::fill_magnitudes], with a `pre_emphasis` coefficient other than
+    /// the one of [Self::set_pre_emphasis]
+    fn fill_magnitudes_with(
+        &self,
+        samples: impl ExactSizeIterator<Item = T>,
+        scratch: &mut Scratch<T>,
+        pre_emphasis: Option<T>,
+    ) -> Result<(), DetectError> {
+        if samples.len() != self.sample_count {
+            return Err(DetectError::SampleCountMismatch {
+                expected: self.sample_count,
+                passed: samples.len(),
+            });
+        }
+        scratch.fft_input.clear();
+        scratch.fft_input.extend(samples);
+        check_finite(&scratch.fft_input)?;
+        let dc_offset = if self.remove_dc {
+            scratch.fft_input.iter().fold(T::zero(), |sum, s| sum + *s)
+                / float(self.sample_count as f64)
+        } else {
+            T::zero()
+        };
+        let mut previous = T::zero();
+        for (s, w) in scratch.fft_input.iter_mut().zip(&self.window) {
+            let sample = *s - dc_offset;
+            let filtered = match pre_emphasis {
+                Some(coefficient) => sample - coefficient * previous,
+                None => sample,
+            };
+            previous = sample;
+            *s = filtered * *w;
+        }
+        scratch.fft_input.resize(self.fft.len(), T::zero());
+
+        let zero = Complex::new(T::zero(), T::zero());
+        scratch.fft_output.resize(self.fft.complex_len(), zero);
+        scratch.fft_scratch.resize(self.fft.get_scratch_len(), zero);
+        // filled by the peak picker and the noise floor of the searched buckets
+        scratch.median_buffer.clear();
+        scratch.median_buffer.reserve(self.fft.complex_len());
+        // the real-input FFT only computes the positive frequencies
+        self.fft
+            .process_with_scratch(
+                &mut scratch.fft_input,
+                &mut scratch.fft_output,
+                &mut scratch.fft_scratch,
+            )
+            .expect("buffers to be sized for the FFT");
+
+        scratch.magnitudes.clear();
+        scratch
+            .magnitudes
+            .extend(scratch.fft_output.iter().map(|c| c.norm()));
+        if scratch.magnitudes.iter().any(|m| m.is_nan()) {
+            return Err(DetectError::NansFound);
+        }
+        self.clean_up_magnitudes(scratch);
+        Ok(())
+    }
+
+    /// Applies [Self::set_notch] and [Self::set_noise_floor_width] to `scratch.magnitudes`
+    fn clean_up_magnitudes(&self, scratch: &mut Scratch<T>) {
+        if let Some(notched) = self.notched_buckets() {
+            scratch.magnitudes[notched].fill(T::zero());
+        }
+        if let Some(width) = self.noise_floor_width {
+            subtract_noise_floor(scratch, width);
+        }
+    }
+
+    /// Positive buckets within the main lobe around the frequency of [Self::set_notch]
+    fn notched_buckets(&self) -> Option<Range<usize>> {
+        let freq = self.notch?;
+        let half_width = float::<T>(self.min_peak_separation as f64) * self.bucket_resolution();
+        Some(self.buckets_within(freq - half_width, freq + half_width))
+    }
+
+    /// `peak` of two adjacent buckets is less than the minimum SNR above the median bucket,
+    /// see [Self::set_min_snr_db]. The `searched` buckets are copied into `median_buffer`
+    /// to find the median.
+    fn below_noise_floor(
+        &self,
+        magnitudes: &[T],
+        searched: Range<usize>,
+        peak: T,
+        median_buffer: &mut Vec<T>,
+    ) -> bool {
+        let Some(db) = self.min_snr_db else {
+            return false;
+        };
+        median_buffer.clear();
+        median_buffer.extend_from_slice(magnitudes.get(searched).unwrap_or(magnitudes));
+        if median_buffer.is_empty() {
+            return false;
+        }
+        let middle = median_buffer.len() / 2;
+        let (_, median, _) =
+            median_buffer.select_nth_unstable_by(middle, |m1, m2| compare(*m1, *m2));
+        // the peak sums up two buckets
+        peak < float::<T>(2.0) * *median * float::<T>(10.0).powf(db / float(20.0))
+    }
+
+    /// Same as [Self::refine_peak] for the window made of `bucket` and its biggest neighbor
+    fn refine_peak_around(
+        &self,
+        magnitudes: &[T],
+        bucket: usize,
+        median_buffer: &mut Vec<T>,
+    ) -> Option<T> {
+        let left = if bucket > 0
+            && (bucket + 1 == magnitudes.len() || magnitudes[bucket - 1] > magnitudes[bucket + 1])
+        {
+            bucket - 1
+        } else {
+            bucket
+        };
+        self.refine_peak(magnitudes, left, median_buffer)
+    }
+
+    /// Frequency of the peak within the window of buckets `left` and `left + 1`,
+    /// `None` if the window is silent. `median_buffer` is reused for the noise floor.
+    fn refine_peak(&self, magnitudes: &[T], left: usize, median_buffer: &mut Vec<T>) -> Option<T> {
+        self.refine_peak_within(magnitudes, left, self.search_buckets(), median_buffer)
+    }
+
+    /// Same as [Self::refine_peak], with the noise floor taken from the `searched` buckets
+    fn refine_peak_within(
+        &self,
+        magnitudes: &[T],
+        left: usize,
+        searched: Range<usize>,
+        median_buffer: &mut Vec<T>,
+    ) -> Option<T> {
+        let window = [magnitudes[left], magnitudes[left + 1]];
+        if window[0] + window[1] < self.silence_threshold
+            || self.below_noise_floor(magnitudes, searched, window[0] + window[1], median_buffer)
+        {
+            return None;
+        }
+        let peak = if window[1] > window[0] {
+            left + 1
+        } else {
+            left
+        };
+        // take weighted average of the two biggest,
+        // corrected for the shape of the window's main lobe
+        let right_weight = window[1] / (window[0] + window[1]);
+        let offset = self.peak_offsets.offset(right_weight);
+        let half = float::<T>(0.5);
+        if (offset - half).abs() < float(PEAK_TIE_TOLERANCE) {
+            // neither bucket is the peak, don't let the comparison above pick one
+            return Some(self.bucket_to_output(float::<T>(left as f64) + half));
+        }
+
+        let bucket = match self.interpolation {
+            Interpolation::WeightedAverage => float::<T>(left as f64) + offset,
+            Interpolation::Parabolic if peak > 0 && peak + 1 < magnitudes.len() => {
+                let [a, b, c] = [peak - 1, peak, peak + 1].map(|b| magnitudes[b].ln());
+                let offset = half * (a - c) / (a - float::<T>(2.0) * b + c);
+                if offset.is_finite() {
+                    float::<T>(peak as f64) + offset.max(-half).min(half)
+                } else {
+                    float(peak as f64)
+                }
+            }
+            Interpolation::Parabolic | Interpolation::None => float(peak as f64),
+        };
+        Some(self.bucket_to_output(bucket))
+    }
+
+    /// Converts a fractional bucket to [Self::set_output_unit]
+    fn bucket_to_output(&self, bucket: T) -> T {
+        match self.output_unit {
+            OutputUnit::Hertz => self.fft_bucket_to_freq(bucket),
+            OutputUnit::BucketIndex => bucket,
+            OutputUnit::NormalizedCyclesPerSample => bucket / float(self.fft.len() as f64),
+        }
+    }
+
+    /// Inverse of [Self::bucket_to_output] into Hz
+    pub(crate) fn output_to_freq(&self, value: T) -> T {
+        match self.output_unit {
+            OutputUnit::Hertz => value,
+            OutputUnit::BucketIndex => self.fft_bucket_to_freq(value),
+            OutputUnit::NormalizedCyclesPerSample => value * float(self.sample_rate as f64),
+        }
+    }
+
+    /// Frequency of a bucket of [Self::spectrum], fractional buckets are allowed
+    pub fn fft_bucket_to_freq(&self, bucket: T) -> T {
+        bucket * self.bucket_resolution()
+    }
+
+    /// Bucket of [Self::spectrum] closest to `freq`, clamped to the ones that exist
+    pub fn freq_to_bucket(&self, freq: T) -> usize {
+        let last = self.fft.complex_len() - 1;
+        (freq / self.bucket_resolution())
+            .round()
+            .to_usize()
+            .map_or(0, |bucket| bucket.min(last))
+    }
+
+    /// Width of a bucket in Hz, `sample_rate / sample_count`.
+    ///
+    /// Zero-padding makes buckets narrower, `sample_rate / fft_len` with the length
+    /// set by [FreqDetectorBuilder::zero_pad_to], but tones closer than
+    /// `sample_rate / sample_count` still can not be told apart.
+    pub fn bucket_resolution(&self) -> T {
+        float::<T>(self.sample_rate as f64) / float(self.fft.len() as f64)
+    }
+}
+
+#[cfg(feature = "std")]
+/// Length of the FFT for `sample_count` samples zero-padded to `zero_pad_to`
+fn padded_len(sample_count: usize, zero_pad_to: Option<usize>) -> usize {
+    zero_pad_to.map_or(sample_count, |len| len.max(sample_count))
+}
+
+/// Lowest level reported by [amplitude_to_dbfs], instead of negative infinity for silence
+pub const DBFS_FLOOR: f64 = -120.0;
+
+#[cfg(feature = "std")]
+/// Converts a linear amplitude, where `1.0` is full scale, to decibels relative to full scale.
+///
+/// Levels below [DBFS_FLOOR] are clamped to it.
+pub fn amplitude_to_dbfs<T: FftNum + Float>(amplitude: T) -> T {
+    let floor = float(DBFS_FLOOR);
+    if amplitude <= T::zero() {
+        return floor;
+    }
+    (float::<T>(20.0) * amplitude.log10()).max(floor)
+}
+
+#[cfg(feature = "std")]
+/// Rejects `NaN`s and infinities before they turn the whole spectrum into garbage
+fn check_finite<T: Float>(samples: &[T]) -> Result<(), DetectError> {
+    match samples.iter().find(|s| !s.is_finite()) {
+        Some(s) if s.is_nan() => Err(DetectError::NansFound),
+        Some(_) => Err(DetectError::NonFinite),
+        None => Ok(()),
+    }
+}
+
+#[cfg(feature = "std")]
+/// Subtracts the running median over `width` buckets from `scratch.magnitudes`
+fn subtract_noise_floor<T: Float>(scratch: &mut Scratch<T>, width: usize) {
+    let magnitudes = &mut scratch.magnitudes;
+    let half_width = width / 2;
+    scratch.noise_floor.clear();
+    for bucket in 0..magnitudes.len() {
+        let neighborhood =
+            bucket.saturating_sub(half_width)..(bucket + half_width + 1).min(magnitudes.len());
+        scratch.median_window.clear();
+        scratch
+            .median_window
+            .extend_from_slice(&magnitudes[neighborhood]);
+        let middle = scratch.median_window.len() / 2;
+        let (_, median, _) = scratch
+            .median_window
+            .select_nth_unstable_by(middle, |a, b| compare(*a, *b));
+        scratch.noise_floor.push(*median);
+    }
+    for (magnitude, floor) in magnitudes.iter_mut().zip(&scratch.noise_floor) {
+        *magnitude = (*magnitude - *floor).max(T::zero());
+    }
+}
+
+#[cfg(feature = "std")]
+/// Peaks closer than this many buckets to the midpoint between two buckets
+/// are a tie, see [Interpolation]
+const PEAK_TIE_TOLERANCE: f64 = 0.01;
+
+#[cfg(feature = "std")]
+/// Fundamentals searched by [DetectionMethod::Cepstrum] without a frequency range
+const CEPSTRUM_FREQ_RANGE: (f64, f64) = (50.0, 1000.0);
+
+#[cfg(feature = "std")]
+/// Threshold of [DetectionMethod::Yin]
+const YIN_THRESHOLD: f64 = 0.1;
+
+#[cfg(feature = "std")]
+/// Peaks weaker than this share of the strongest one are not considered
+/// by the multi-peak methods
+const MIN_PEAK_SHARE: f64 = 0.1;
+
+#[cfg(feature = "std")]
+fn float<T: FftNum>(value: f64) -> T {
+    T::from_f64(value).expect("f64 to be convertible to any FFT float")
+}
+
+#[cfg(feature = "std")]
+/// Magnitudes are checked for NaNs, so they are always comparable
+fn compare<T: Float>(a: T, b: T) -> Ordering {
+    a.partial_cmp(&b).unwrap_or(Ordering::Equal)
+}
+
+#[derive(Error, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DetectError {
+    #[error("Invalid sample count passed (expected {expected}, passed {passed})")]
+    SampleCountMismatch { expected: usize, passed: usize },
+    #[error("NaNs in the samples")]
+    NansFound,
+    #[error("Infinities in the samples")]
+    NonFinite,
+    #[error("Fewer than 2 frequency buckets to search for a peak")]
+    EmptySpectrum,
+    #[error("Channel {channel} selected out of {channels} channels")]
+    NoSuchChannel { channel: usize, channels: usize },
+    #[error("No channels in the interleaved samples")]
+    NoChannels,
+    #[error("{passed} interleaved samples are not whole frames of {channels} channels")]
+    IncompleteFrame { passed: usize, channels: usize },
+}
+
+#[derive(Error, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DetectorCreateError {
+    #[error("Detector does not support sample rate < 1 sample per second")]
+    SampleRateTooLow,
+    #[error("Needs at least 4 samples for detection")]
+    TooFewSamples,
+    #[error("Hop size must be at least 1 sample")]
+    HopSizeTooSmall,
+    #[error("Threshold must be between 0 and 1")]
+    InvalidThreshold,
+    #[error("Minimum frequency must be below the maximum frequency")]
+    EmptyFreqRange,
+    #[error("Frequency range lies above the Nyquist frequency")]
+    FreqRangeAboveNyquist,
+    #[error("Frequency range must contain at least 2 FFT buckets")]
+    FreqRangeTooNarrow,
+    #[error("Target frequencies must be between 0 and the Nyquist frequency")]
+    InvalidTargetFreq,
+    #[error("FFT length must match the sample count")]
+    FftLengthMismatch,
+    #[error("Smoothing factor must be above 0 and at most 1")]
+    InvalidSmoothing,
+    #[error("Needs at least 1 bin per octave")]
+    TooFewBinsPerOctave,
+    #[error("Sample count must be a power of two up to 4096 for microfft")]
+    UnsupportedSampleCount,
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use std::sync::Arc;
+
+    use super::{
+        amplitude_to_dbfs, ChannelMode, DetectError, Detection, DetectionMethod,
+        DetectorCreateError, FreqDetector, Interpolation, OutputUnit, WindowFunction,
+    };
+
+    #[test]
+    fn freq_detector_smoke_test() {
+        use std::f32::consts::TAU;
+        let sample_count = 4096 * 2;
+        let freq_detector = FreqDetector::new(44100, sample_count).unwrap();
+
+        for freq in [10, 20, 30, 100, 1000, 2000] {
+            let sin_samples = (0..sample_count)
+                .map(|i| {
+                    (i as f32 / 44100.0 * freq as f32 * TAU).sin()
+                        // noise
+                        + 0.9 * (i as f32 / 44100.0 * 101.0 * TAU).sin()
+                        + 0.9 * (i as f32 / 44100.0 * 120.0 * TAU).sin()
+                })
+                .collect::<Vec<_>>();
+
+            let detected_freq = freq_detector.detect(&sin_samples).unwrap();
+            dbg!(detected_freq, freq);
+            assert!(
+                (detected_freq - freq as f32).abs() < 0.5,
+                "detected {detected_freq} expected {freq}"
+            );
+        }
+    }
+
+    #[test]
+    fn hann_window_beats_rectangular_off_bucket() {
+        use std::f32::consts::TAU;
+        let sample_count = 4096;
+        // 443 Hz sits ~13% of a bucket away from the closest bucket center
+        let freq = 443.0;
+        let sin_samples = (0..sample_count)
+            .map(|i| (i as f32 / 44100.0 * freq * TAU).sin())
+            .collect::<Vec<_>>();
+
+        let error = |window| {
+            let freq_detector = FreqDetector::with_window(44100, sample_count, window).unwrap();
+            (freq_detector.detect(&sin_samples).unwrap() - freq).abs()
+        };
+        let rectangular_error = error(WindowFunction::Rectangular);
+        let hann_error = error(WindowFunction::Hann);
+        assert!(
+            hann_error < rectangular_error,
+            "hann error {hann_error} rectangular error {rectangular_error}"
+        );
+    }
+
+    #[test]
+    fn spectrum_peaks_at_signal_frequency() {
+        use std::f32::consts::TAU;
+        let sample_count = 4096;
+        let sin_samples = (0..sample_count)
+            .map(|i| (i as f32 / 44100.0 * 1000.0 * TAU).sin())
+            .collect::<Vec<_>>();
+
+        let freq_detector = FreqDetector::new(44100, sample_count).unwrap();
+        let spectrum = freq_detector.spectrum(&sin_samples).unwrap();
+        assert_eq!(spectrum.len(), sample_count / 2 + 1);
+        assert_eq!(spectrum.last().unwrap().0, 22050.0);
+
+        let (peak_freq, _) = spectrum
+            .iter()
+            .copied()
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .unwrap();
+        assert!((peak_freq - 1000.0).abs() < 44100.0 / sample_count as f32);
+
+        let mut with_nan = sin_samples.clone();
+        with_nan[10] = f32::NAN;
+        assert!(freq_detector.spectrum(&with_nan).is_err());
+    }
+
+    #[test]
+    fn detect_n_finds_separate_tones() {
+        use std::f32::consts::TAU;
+        let sample_count = 4096;
+        let tones = [(440.0, 1.0), (1000.0, 0.7), (3000.0, 0.4)];
+        let samples = (0..sample_count)
+            .map(|i| {
+                tones
+                    .iter()
+                    .map(|(freq, amp)| amp * (i as f32 / 44100.0 * freq * TAU).sin())
+                    .sum()
+            })
+            .collect::<Vec<f32>>();
+
+        let freq_detector =
+            FreqDetector::with_window(44100, sample_count, WindowFunction::Hann).unwrap();
+        let peaks = freq_detector.detect_n(&samples, 3).unwrap();
+        assert_eq!(peaks.len(), 3);
+        for (detected, (freq, _)) in peaks.iter().zip(tones) {
+            assert!(
+                (detected - freq).abs() < 0.5,
+                "detected {detected} expected {freq}"
+            );
+        }
+
+        // the main lobe of a single tone is not reported twice
+        let single_tone = (0..sample_count)
+            .map(|i| (i as f32 / 44100.0 * 443.0 * TAU).sin())
+            .collect::<Vec<f32>>();
+        let peaks = freq_detector.detect_n(&single_tone, 2).unwrap();
+        let bucket_width = 44100.0 / sample_count as f32;
+        assert!(
+            (peaks[0] - peaks[1]).abs() > 2.0 * bucket_width,
+            "{peaks:?}"
+        );
+    }
+
+    #[test]
+    fn parabolic_interpolation_refines_off_bucket_peak() {
+        use std::f32::consts::TAU;
+        let sample_count = 4096;
+        let freq = 443.0;
+        let sin_samples = (0..sample_count)
+            .map(|i| (i as f32 / 44100.0 * freq * TAU).sin())
+            .collect::<Vec<_>>();
+
+        let error = |interpolation| {
+            let mut freq_detector =
+                FreqDetector::with_window(44100, sample_count, WindowFunction::Hann).unwrap();
+            freq_detector.set_interpolation(interpolation);
+            (freq_detector.detect(&sin_samples).unwrap() - freq).abs()
+        };
+        let parabolic_error = error(Interpolation::Parabolic);
+        let no_interpolation_error = error(Interpolation::None);
+        assert!(parabolic_error < 0.2, "parabolic error {parabolic_error}");
+        assert!(
+            parabolic_error < no_interpolation_error,
+            "parabolic error {parabolic_error} no interpolation error {no_interpolation_error}"
+        );
+    }
+
+    #[test]
+    fn f64_detection() {
+        use std::f64::consts::TAU;
+        let sample_count = 4096;
+        let freq = 443.25;
+        let sin_samples = (0..sample_count)
+            .map(|i| (i as f64 / 44100.0 * freq * TAU).sin())
+            .collect::<Vec<f64>>();
+
+        let freq_detector =
+            FreqDetector::<f64>::with_window(44100, sample_count, WindowFunction::Hann).unwrap();
+        let detected_freq = freq_detector.detect(&sin_samples).unwrap();
+        assert!(
+            (detected_freq - freq).abs() < 0.01,
+            "detected {detected_freq} expected {freq}"
+        );
+    }
+
+    #[test]
+    fn integer_pcm_detection_matches_f32() {
+        use std::f32::consts::TAU;
+        let sample_count = 4096;
+        let sin_samples = (0..sample_count)
+            .map(|i| 0.5 * (i as f32 / 44100.0 * 1000.0 * TAU).sin())
+            .collect::<Vec<_>>();
+        let pcm_samples = sin_samples
+            .iter()
+            .map(|s| (s * 32768.0) as i16)
+            .collect::<Vec<_>>();
+
+        let freq_detector = FreqDetector::new(44100, sample_count).unwrap();
+        let from_f32 = freq_detector.detect(&sin_samples).unwrap();
+        let from_i16 = freq_detector.detect_i16(&pcm_samples).unwrap();
+        assert!(
+            (from_f32 - from_i16).abs() < 0.01,
+            "{from_f32} vs {from_i16}"
+        );
+        assert!(freq_detector.detect_i16(&pcm_samples[1..]).is_err());
+
+        let pcm_samples = sin_samples
+            .iter()
+            .map(|s| (*s as f64 * 2147483648.0) as i32)
+            .collect::<Vec<_>>();
+        let from_i32 = freq_detector.detect_i32(&pcm_samples).unwrap();
+        assert!(
+            (from_f32 - from_i32).abs() < 0.01,
+            "{from_f32} vs {from_i32}"
+        );
+
+        let pcm_samples = sin_samples
+            .iter()
+            .map(|s| (s * 128.0 + 128.0).round() as u8)
+            .collect::<Vec<_>>();
+        let from_u8 = freq_detector.detect_u8(&pcm_samples).unwrap();
+        assert!((from_f32 - from_u8).abs() < 0.1, "{from_f32} vs {from_u8}");
+    }
+
+    #[test]
+    fn detect_mut_matches_detect() {
+        use std::f32::consts::TAU;
+        let sample_count = 4096;
+        let mut freq_detector =
+            FreqDetector::with_window(44100, sample_count, WindowFunction::Hann).unwrap();
+        for freq in [100.0, 443.0, 1500.0] {
+            let sin_samples = (0..sample_count)
+                .map(|i| (i as f32 / 44100.0 * freq * TAU).sin())
+                .collect::<Vec<_>>();
+            assert_eq!(
+                freq_detector.detect(&sin_samples).unwrap(),
+                freq_detector.detect_mut(&sin_samples).unwrap()
+            );
+        }
+        assert!(freq_detector.detect_mut(&[0.0; 10]).is_err());
+    }
+
+    #[test]
+    fn confidence_separates_tone_from_noise() {
+        use std::f32::consts::TAU;
+        let sample_count = 4096;
+        let freq_detector =
+            FreqDetector::with_window(44100, sample_count, WindowFunction::Hann).unwrap();
+
+        let tone = (0..sample_count)
+            .map(|i| (i as f32 / 44100.0 * 443.0 * TAU).sin())
+            .collect::<Vec<_>>();
+        let (_, tone_confidence) = freq_detector.detect_with_confidence(&tone).unwrap();
+        assert!(tone_confidence > 0.9, "tone confidence {tone_confidence}");
+
+        // deterministic white noise from a linear congruential generator
+        let mut state = 12345u32;
+        let noise = (0..sample_count)
+            .map(|_| {
+                state = state.wrapping_mul(1664525).wrapping_add(1013904223);
+                state as f32 / u32::MAX as f32 - 0.5
+            })
+            .collect::<Vec<_>>();
+        let (_, noise_confidence) = freq_detector.detect_with_confidence(&noise).unwrap();
+        assert!(
+            noise_confidence < 0.1,
+            "noise confidence {noise_confidence}"
+        );
+
+        let (_, silence_confidence) = freq_detector
+            .detect_with_confidence(&vec![0.0; sample_count])
+            .unwrap();
+        assert_eq!(silence_confidence, 0.0);
+    }
+
+    #[test]
+    fn harmonic_product_spectrum_finds_weak_fundamental() {
+        use std::f32::consts::TAU;
+        let sample_count = 4096;
+        let fundamental = 220.0;
+        // sawtooth-like series of harmonics, but with a weak fundamental
+        let samples = (0..sample_count)
+            .map(|i| {
+                (1..=8)
+                    .map(|h| {
+                        let amplitude = if h == 1 { 0.4 } else { 1.0 / (h - 1) as f32 };
+                        amplitude * (i as f32 / 44100.0 * fundamental * h as f32 * TAU).sin()
+                    })
+                    .sum()
+            })
+            .collect::<Vec<f32>>();
+
+        let freq_detector =
+            FreqDetector::with_window(44100, sample_count, WindowFunction::Hann).unwrap();
+        let strongest = freq_detector
+            .detect_with_method(&samples, DetectionMethod::StrongestPeak)
+            .unwrap();
+        assert!((strongest - 2.0 * fundamental).abs() < 1.0, "{strongest}");
+
+        let hps = freq_detector
+            .detect_with_method(
+                &samples,
+                DetectionMethod::HarmonicProductSpectrum { harmonics: 3 },
+            )
+            .unwrap();
+        assert!((hps - fundamental).abs() < 1.0, "{hps}");
+    }
+
+    #[test]
+    fn subharmonic_summation_finds_weak_fundamentals() {
+        use std::f32::consts::TAU;
+        let sample_count = 8192;
+        // A1 of a piano, with no fundamental at all and strong higher harmonics
+        let fundamental = 55.0;
+        let samples = (0..sample_count)
+            .map(|i| {
+                (1..=10)
+                    .map(|h| {
+                        let amplitude = if h == 1 { 0.0 } else { 1.0 / (h - 1) as f32 };
+                        amplitude * (i as f32 / 44100.0 * fundamental * h as f32 * TAU).sin()
+                    })
+                    .sum()
+            })
+            .collect::<Vec<f32>>();
+
+        let freq_detector =
+            FreqDetector::with_window(44100, sample_count, WindowFunction::Hann).unwrap();
+        let shs = freq_detector
+            .detect_with_method(
+                &samples,
+                DetectionMethod::SubharmonicSummation { harmonics: 8 },
+            )
+            .unwrap();
+        assert!((shs - fundamental).abs() < 0.2, "{shs}");
+
+        let silence = vec![0.0; sample_count];
+        let method = DetectionMethod::SubharmonicSummation { harmonics: 8 };
+        assert_eq!(
+            freq_detector.detect_with_method(&silence, method).unwrap(),
+            0.0
+        );
+    }
+
+    #[test]
+    fn freq_range_ignores_out_of_band_peaks() {
+        use std::f32::consts::TAU;
+        let sample_count = 4096;
+        let samples = (0..sample_count)
+            .map(|i| {
+                // loud rumble and a quiet bird call
+                2.0 * (i as f32 / 44100.0 * 50.0 * TAU).sin()
+                    + 0.3 * (i as f32 / 44100.0 * 4321.0 * TAU).sin()
+            })
+            .collect::<Vec<f32>>();
+
+        let mut freq_detector =
+            FreqDetector::with_window(44100, sample_count, WindowFunction::Hann).unwrap();
+        assert!((freq_detector.detect(&samples).unwrap() - 50.0).abs() < 0.5);
+
+        freq_detector.set_freq_range(2000.0, 8000.0).unwrap();
+        let detected = freq_detector.detect(&samples).unwrap();
+        assert!((detected - 4321.0).abs() < 0.5, "detected {detected}");
+        assert_eq!(freq_detector.detect_n(&samples, 2).unwrap().len(), 2);
+
+        assert!(matches!(
+            freq_detector.set_freq_range(8000.0, 2000.0),
+            Err(DetectorCreateError::EmptyFreqRange)
+        ));
+        assert!(matches!(
+            freq_detector.set_freq_range(30000.0, 40000.0),
+            Err(DetectorCreateError::FreqRangeAboveNyquist)
+        ));
+        assert!(matches!(
+            freq_detector.set_freq_range(1000.0, 1001.0),
+            Err(DetectorCreateError::FreqRangeTooNarrow)
+        ));
+    }
+
+    #[test]
+    fn dc_offset_is_removed() {
+        use std::f32::consts::TAU;
+        let sample_count = 4096;
+        let freq = 60.0;
+        let biased_samples = (0..sample_count)
+            .map(|i| 1.0 + 0.5 * (i as f32 / 44100.0 * freq * TAU).sin())
+            .collect::<Vec<f32>>();
+
+        let mut freq_detector = FreqDetector::new(44100, sample_count).unwrap();
+        let detected = freq_detector.detect(&biased_samples).unwrap();
+        assert!((detected - freq).abs() < 0.5, "detected {detected}");
+
+        freq_detector.set_remove_dc(false);
+        let detected = freq_detector.detect(&biased_samples).unwrap();
+        assert!((detected - freq).abs() > 5.0, "detected {detected}");
+    }
+
+    #[test]
+    fn silence_is_reported_separately() {
+        use std::f32::consts::TAU;
+        let sample_count = 4096;
+        let mut freq_detector = FreqDetector::new(44100, sample_count).unwrap();
+        assert_eq!(
+            freq_detector
+                .detect_pitch(&vec![0.0; sample_count])
+                .unwrap(),
+            Detection::Silent
+        );
+
+        let quiet = (0..sample_count)
+            .map(|i| 0.001 * (i as f32 / 44100.0 * 100.0 * TAU).sin())
+            .collect::<Vec<f32>>();
+        let Detection::Pitch(freq) = freq_detector.detect_pitch(&quiet).unwrap() else {
+            panic!("quiet tone is not silence by default");
+        };
+        assert!((freq - 100.0).abs() < 0.5);
+
+        freq_detector.set_silence_threshold(10.0);
+        assert_eq!(
+            freq_detector.detect_pitch(&quiet).unwrap(),
+            Detection::Silent
+        );
+        assert_eq!(freq_detector.detect(&quiet).unwrap(), 0.0);
+    }
+
+    #[test]
+    fn level_is_reported_with_frequency() {
+        use std::f32::consts::TAU;
+        let sample_count = 4096;
+        let samples = (0..sample_count)
+            .map(|i| 0.5 * (i as f32 / 44100.0 * 1000.0 * TAU).sin())
+            .collect::<Vec<f32>>();
+
+        let freq_detector = FreqDetector::new(44100, sample_count).unwrap();
+        let (freq, level) = freq_detector.detect_with_level(&samples).unwrap();
+        assert!((freq - 1000.0).abs() < 0.5);
+        assert!((level - 0.5 / 2f32.sqrt()).abs() < 0.001, "level {level}");
+        assert!((amplitude_to_dbfs(level) + 9.03).abs() < 0.01);
+        assert_eq!(amplitude_to_dbfs(0.0f32), -120.0);
+    }
+
+    #[test]
+    fn batch_detects_every_chunk() {
+        use std::f32::consts::TAU;
+        let sample_count = 4096;
+        let freqs = [220.0, 440.0, 880.0];
+        let mut samples = freqs
+            .iter()
+            .flat_map(|freq| {
+                (0..sample_count).map(move |i| (i as f32 / 44100.0 * freq * TAU).sin())
+            })
+            .collect::<Vec<f32>>();
+        samples[sample_count + 10] = f32::NAN;
+        // trailing partial chunk
+        samples.extend([0.0; 100]);
+
+        let freq_detector = FreqDetector::new(44100, sample_count).unwrap();
+        let detections = freq_detector.detect_batch(&samples);
+        assert_eq!(detections.len(), 3);
+        assert!((detections[0].as_ref().unwrap() - freqs[0]).abs() < 0.5);
+        assert!(detections[1].is_err());
+        assert!((detections[2].as_ref().unwrap() - freqs[2]).abs() < 0.5);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn parallel_batch_matches_serial() {
+        use std::f32::consts::TAU;
+        let sample_count = 4096;
+        let samples = (0..sample_count * 16)
+            .map(|i| {
+                let freq = 100.0 + (i / sample_count) as f32 * 50.0;
+                (i as f32 / 44100.0 * freq * TAU).sin()
+            })
+            .collect::<Vec<f32>>();
+
+        let freq_detector = FreqDetector::new(44100, sample_count).unwrap();
+        let serial = freq_detector
+            .detect_batch(&samples)
+            .into_iter()
+            .map(Result::unwrap)
+            .collect::<Vec<_>>();
+        let parallel = freq_detector
+            .detect_batch_par(&samples)
+            .into_iter()
+            .map(Result::unwrap)
+            .collect::<Vec<_>>();
+        assert_eq!(serial, parallel);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn parallel_detect_all_matches_serial() {
+        use std::f32::consts::TAU;
+        let sample_count = 1024;
+        let samples = (0..44100)
+            .map(|i| {
+                let freq = 200.0 + i as f32 / 100.0;
+                (i as f32 / 44100.0 * freq * TAU).sin()
+            })
+            .collect::<Vec<f32>>();
+
+        let freq_detector = FreqDetector::new(44100, sample_count).unwrap();
+        assert_eq!(
+            freq_detector.detect_all(&samples, 300).unwrap(),
+            freq_detector.detect_all_par(&samples, 300).unwrap()
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn results_round_trip_through_json() {
+        use crate::{DetectionResult, PitchTrack};
+        use std::f32::consts::TAU;
+        let samples = (0..4096)
+            .map(|i| (i as f32 / 44100.0 * 440.0 * TAU).sin())
+            .collect::<Vec<f32>>();
+        let freq_detector = FreqDetector::new(44100, 4096).unwrap();
+
+        let result = freq_detector.detect_full(&samples).unwrap();
+        let json = serde_json::to_string(&result).unwrap();
+        assert_eq!(
+            serde_json::from_str::<DetectionResult>(&json).unwrap(),
+            result
+        );
+
+        let mut track = PitchTrack::new();
+        track.push(0.0, Some(440.0));
+        track.push(0.1, None);
+        let json = serde_json::to_string(&track).unwrap();
+        assert_eq!(serde_json::from_str::<PitchTrack>(&json).unwrap(), track);
+
+        let error = freq_detector.detect(&samples[..100]).unwrap_err();
+        let json = serde_json::to_string(&error).unwrap();
+        assert!(matches!(
+            serde_json::from_str(&json).unwrap(),
+            DetectError::SampleCountMismatch {
+                expected: 4096,
+                passed: 100
+            }
+        ));
+    }
+
+    #[test]
+    fn smallest_sample_count_does_not_panic() {
+        let freq_detector = FreqDetector::new(8, 4).unwrap();
+        let detected: f32 = freq_detector.detect(&[0.0, 1.0, 0.0, -1.0]).unwrap();
+        assert!((detected - 2.0).abs() < 1e-3, "detected {detected}");
+        assert_eq!(freq_detector.detect(&[0.0; 4]).unwrap(), 0.0);
+    }
+
+    #[test]
+    fn pre_emphasis_boosts_high_frequencies() {
+        use std::f32::consts::TAU;
+        let samples = (0..4096)
+            .map(|i| {
+                let t = i as f32 / 44100.0;
+                (t * 100.0 * TAU).sin() + 0.5 * (t * 3000.0 * TAU).sin()
+            })
+            .collect::<Vec<f32>>();
+        let mut freq_detector =
+            FreqDetector::with_window(44100, 4096, WindowFunction::Hann).unwrap();
+        let high_to_low = |spectrum: Vec<(f32, f32)>| {
+            let magnitude_at = |freq: f32| {
+                spectrum
+                    .iter()
+                    .min_by(|a, b| (a.0 - freq).abs().total_cmp(&(b.0 - freq).abs()))
+                    .unwrap()
+                    .1
+            };
+            magnitude_at(3000.0) / magnitude_at(100.0)
+        };
+
+        let plain = high_to_low(freq_detector.spectrum(&samples).unwrap());
+        assert!((freq_detector.detect(&samples).unwrap() - 100.0).abs() < 1.0);
+
+        freq_detector.set_pre_emphasis(Some(0.97));
+        let emphasized = high_to_low(freq_detector.spectrum(&samples).unwrap());
+        assert!(emphasized > plain * 5.0, "{emphasized} vs {plain}");
+        assert!((freq_detector.detect(&samples).unwrap() - 3000.0).abs() < 1.0);
+    }
+
+    #[test]
+    fn cents_from_target() {
+        use std::f32::consts::TAU;
+        let samples = (0..8192)
+            .map(|i| (i as f32 / 44100.0 * 446.0 * TAU).sin())
+            .collect::<Vec<f32>>();
+        let freq_detector = FreqDetector::with_window(44100, 8192, WindowFunction::Hann).unwrap();
+        let cents = freq_detector
+            .detect_cents(&samples, 440.0)
+            .unwrap()
+            .unwrap();
+        let expected = 1200.0 * (446.0f32 / 440.0).log2();
+        assert!((cents - expected).abs() < 1.0, "{cents} vs {expected}");
+
+        assert_eq!(
+            freq_detector.detect_cents(&[0.0; 8192], 440.0).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn sample_count_can_be_changed() {
+        use std::f32::consts::TAU;
+        let samples = |count: usize| {
+            (0..count)
+                .map(|i| (i as f32 / 44100.0 * 443.0 * TAU).sin())
+                .collect::<Vec<f32>>()
+        };
+        let mut freq_detector =
+            FreqDetector::with_window(44100, 2048, WindowFunction::Hann).unwrap();
+        for sample_count in [8192, 1024, 2048] {
+            freq_detector.set_sample_count(sample_count).unwrap();
+            assert_eq!(freq_detector.sample_count(), sample_count);
+            let detected = freq_detector.detect_mut(&samples(sample_count)).unwrap();
+            assert!((detected - 443.0).abs() < 0.5, "{sample_count}: {detected}");
+        }
+        assert!(freq_detector.detect(&samples(8192)).is_err());
+
+        assert!(matches!(
+            freq_detector.set_sample_count(2),
+            Err(DetectorCreateError::TooFewSamples)
+        ));
+        freq_detector.set_freq_range(400.0, 500.0).unwrap();
+        assert!(matches!(
+            freq_detector.set_sample_count(64),
+            Err(DetectorCreateError::FreqRangeTooNarrow)
+        ));
+        assert_eq!(freq_detector.sample_count(), 2048);
+    }
+
+    #[test]
+    fn non_finite_samples_are_rejected() {
+        let freq_detector = FreqDetector::new(44100, 1024).unwrap();
+        let mut samples = vec![0.0f32; 1024];
+        samples[3] = f32::INFINITY;
+        assert!(matches!(
+            freq_detector.detect(&samples),
+            Err(DetectError::NonFinite)
+        ));
+        samples[3] = f32::NEG_INFINITY;
+        assert!(matches!(
+            freq_detector.detect_pitch(&samples),
+            Err(DetectError::NonFinite)
+        ));
+        samples[3] = f32::NAN;
+        assert!(matches!(
+            freq_detector.detect(&samples),
+            Err(DetectError::NansFound)
+        ));
+    }
+
+    #[test]
+    fn spectrogram_follows_the_tone() {
+        use std::f32::consts::TAU;
+        let sample_count = 1024;
+        let samples = (0..sample_count * 8)
+            .map(|i| {
+                let freq = if i < sample_count * 4 { 500.0 } else { 2000.0 };
+                (i as f32 / 44100.0 * freq * TAU).sin()
+            })
+            .collect::<Vec<f32>>();
+        let freq_detector =
+            FreqDetector::with_window(44100, sample_count, WindowFunction::Hann).unwrap();
+
+        let spectrogram = freq_detector.spectrogram(&samples, 512).unwrap();
+        assert_eq!(spectrogram.len(), (samples.len() - sample_count) / 512 + 1);
+        let strongest_freq = |column: &Vec<f32>| {
+            let (bucket, _) = column
+                .iter()
+                .enumerate()
+                .max_by(|a, b| a.1.total_cmp(b.1))
+                .unwrap();
+            bucket as f32 * 44100.0 / sample_count as f32
+        };
+        let bucket_width = 44100.0 / sample_count as f32;
+        for column in &spectrogram {
+            assert_eq!(column.len(), sample_count / 2 + 1);
+        }
+        assert!((strongest_freq(&spectrogram[0]) - 500.0).abs() < bucket_width);
+        assert!((strongest_freq(spectrogram.last().unwrap()) - 2000.0).abs() < bucket_width);
+    }
+
+    #[test]
+    fn peak_magnitude_tracks_amplitude() {
+        use std::f32::consts::TAU;
+        let sample_count = 4096;
+        let tone = |amplitude: f32| {
+            (0..sample_count)
+                .map(|i| {
+                    let t = i as f32 / 44100.0;
+                    amplitude * (t * 443.0 * TAU).sin() + 0.05 * (t * 3000.0 * TAU).sin()
+                })
+                .collect::<Vec<f32>>()
+        };
+        let freq_detector = FreqDetector::new(44100, sample_count).unwrap();
+
+        let (loud_freq, loud) = freq_detector.detect_with_magnitude(&tone(0.8)).unwrap();
+        let (quiet_freq, quiet) = freq_detector.detect_with_magnitude(&tone(0.4)).unwrap();
+        assert!((loud_freq - 443.0).abs() < 1.0 && (quiet_freq - 443.0).abs() < 1.0);
+        assert!((loud / quiet - 2.0).abs() < 0.01, "{loud} vs {quiet}");
+        assert!(loud > 0.8 * sample_count as f32 / 2.0 * 0.9);
+    }
+
+    #[test]
+    fn harmonic_spacing_finds_missing_fundamental() {
+        use std::f32::consts::TAU;
+        let sample_count = 8192;
+        let samples = (0..sample_count)
+            .map(|i| {
+                [(400.0, 1.0), (600.0, 0.8), (800.0, 0.6)]
+                    .iter()
+                    .map(|(freq, amp)| amp * (i as f32 / 44100.0 * freq * TAU).sin())
+                    .sum()
+            })
+            .collect::<Vec<f32>>();
+        let freq_detector =
+            FreqDetector::with_window(44100, sample_count, WindowFunction::Hann).unwrap();
+
+        let strongest = freq_detector.detect(&samples).unwrap();
+        assert!((strongest - 400.0).abs() < 1.0, "{strongest}");
+        let spacing = freq_detector
+            .detect_with_method(&samples, DetectionMethod::HarmonicSpacing { peaks: 5 })
+            .unwrap();
+        assert!((spacing - 200.0).abs() < 0.5, "{spacing}");
+
+        let silence = freq_detector
+            .detect_with_method(
+                &vec![0.0; sample_count],
+                DetectionMethod::HarmonicSpacing { peaks: 5 },
+            )
+            .unwrap();
+        assert_eq!(silence, 0.0);
+    }
+
+    #[test]
+    fn full_detection() {
+        use std::f32::consts::TAU;
+        let samples = (0..4096)
+            .map(|i| (i as f32 / 44100.0 * 1000.0 * TAU).sin())
+            .collect::<Vec<f32>>();
+        let freq_detector = FreqDetector::new(44100, 4096).unwrap();
+
+        let result = freq_detector.detect_full(&samples).unwrap();
+        assert!((result.frequency - 1000.0).abs() < 1.0);
+        assert_eq!(result.frequency, freq_detector.detect(&samples).unwrap());
+        assert_eq!(
+            result.bucket,
+            (1000.0f32 * 4096.0 / 44100.0).round() as usize
+        );
+        assert!(result.confidence > 0.9);
+        assert!(result.magnitude > 4096.0 / 2.0 * 0.9);
+        assert!(!result.is_silent);
+
+        let silence = freq_detector.detect_full(&[0.0; 4096]).unwrap();
+        assert!(silence.is_silent);
+        assert_eq!(silence.frequency, 0.0);
+    }
+
+    #[test]
+    fn chord_notes() {
+        use crate::NoteName;
+        use std::f32::consts::TAU;
+        let sample_count = 8192;
+        // C major triad, with a second harmonic on the C
+        let partials = [(261.63, 1.0), (329.63, 0.8), (392.0, 0.9), (523.25, 0.5)];
+        let samples = (0..sample_count)
+            .map(|i| {
+                partials
+                    .iter()
+                    .map(|(freq, amp)| amp * (i as f32 / 44100.0 * freq * TAU).sin())
+                    .sum()
+            })
+            .collect::<Vec<f32>>();
+        let freq_detector =
+            FreqDetector::with_window(44100, sample_count, WindowFunction::Hann).unwrap();
+
+        let chord = freq_detector
+            .detect_chord(&samples)
+            .unwrap()
+            .iter()
+            .map(|note| (note.name, note.octave))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            chord,
+            [(NoteName::C, 4), (NoteName::E, 4), (NoteName::G, 4)]
+        );
+        assert!(freq_detector
+            .detect_chord(&vec![0.0; sample_count])
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn windows_are_detected_lazily() {
+        use std::f32::consts::TAU;
+        let sample_count = 2048;
+        let samples = (0..sample_count * 10)
+            .map(|i| (i as f32 / 44100.0 * 880.0 * TAU).sin())
+            .collect::<Vec<f32>>();
+        let freq_detector = FreqDetector::new(44100, sample_count).unwrap();
+
+        let hop = sample_count / 4;
+        assert_eq!(
+            freq_detector.detect_windows(&samples, hop).count(),
+            (samples.len() - sample_count) / hop + 1
+        );
+        let first = freq_detector
+            .detect_windows(&samples, hop)
+            .take(3)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(first.len(), 3);
+        for (i, detected) in first.into_iter().enumerate() {
+            let window = &samples[i * hop..i * hop + sample_count];
+            assert_eq!(detected, freq_detector.detect(window).unwrap());
+        }
+    }
+
+    #[test]
+    fn peak_picker_finds_weaker_fundamental() {
+        use crate::{LowestStrongPicker, PeakPicker};
+        use std::f32::consts::TAU;
+        let sample_count = 4096;
+        let samples = (0..sample_count)
+            .map(|i| {
+                let t = i as f32 / 44100.0;
+                0.5 * (t * 220.0 * TAU).sin() + (t * 440.0 * TAU).sin()
+            })
+            .collect::<Vec<f32>>();
+
+        let strongest = FreqDetector::new(44100, sample_count).unwrap();
+        assert!((strongest.detect(&samples).unwrap() - 440.0).abs() < 1.0);
+
+        let lowest = FreqDetector::builder(44100, sample_count)
+            .peak_picker(LowestStrongPicker { ratio: 0.3 })
+            .build()
+            .unwrap();
+        assert!((lowest.detect(&samples).unwrap() - 220.0).abs() < 1.0);
+        assert!((lowest.clone().detect(&samples).unwrap() - 220.0).abs() < 1.0);
+
+        // a custom picker returning nonsense is kept within the spectrum
+        #[derive(Debug)]
+        struct Past;
+        impl PeakPicker<f32> for Past {
+            fn pick(&self, _: &[f32]) -> usize {
+                usize::MAX
+            }
+        }
+        let mut past = FreqDetector::new(44100, sample_count).unwrap();
+        past.set_peak_picker(Past);
+        past.detect(&samples).unwrap();
+    }
+
+    #[test]
+    fn detects_all_windows_with_timestamps() {
+        use std::f32::consts::TAU;
+        let sample_count = 1024;
+        // a second of 440 Hz, then a second of silence
+        let samples = (0..44100 * 2)
+            .map(|i| {
+                if i < 44100 {
+                    (i as f32 / 44100.0 * 440.0 * TAU).sin()
+                } else {
+                    0.0
+                }
+            })
+            .collect::<Vec<f32>>();
+        let freq_detector = FreqDetector::new(44100, sample_count).unwrap();
+
+        let hop = 441;
+        let detections = freq_detector.detect_all(&samples, hop).unwrap();
+        assert_eq!(detections.len(), (samples.len() - sample_count) / hop + 1);
+        for (i, detection) in detections.iter().enumerate() {
+            assert_eq!(detection.sample, i * hop);
+            assert!(
+                (detection.time - i as f32 / 100.0).abs() < 1e-4,
+                "{detection:?}"
+            );
+            let window = &samples[detection.sample..detection.sample + sample_count];
+            let full = freq_detector.detect_full(window).unwrap();
+            assert_eq!(
+                (detection.frequency, detection.confidence),
+                (full.frequency, full.confidence)
+            );
+        }
+        assert!((detections[10].frequency - 440.0).abs() < 1.0);
+        assert_eq!(detections.last().unwrap().frequency, 0.0);
+
+        let mut broken = samples.clone();
+        broken[50000] = f32::NAN;
+        assert!(matches!(
+            freq_detector.detect_all(&broken, hop),
+            Err(DetectError::NansFound)
+        ));
+    }
+
+    #[test]
+    fn vibrato_of_a_sung_note() {
+        use std::f64::consts::TAU;
+        let mut phase = 0.0;
+        let samples = (0..44100 * 2)
+            .map(|i| {
+                let time = i as f64 / 44100.0;
+                let cents = 30.0 * (time * 5.5 * TAU).sin();
+                phase += 440.0 * 2.0f64.powf(cents / 1200.0) / 44100.0 * TAU;
+                phase.sin() as f32
+            })
+            .collect::<Vec<f32>>();
+        let freq_detector = FreqDetector::with_window(44100, 2048, WindowFunction::Hann).unwrap();
+
+        let found = freq_detector
+            .detect_vibrato(&samples, 256)
+            .unwrap()
+            .unwrap();
+        assert!((found.rate - 5.5).abs() < 0.1, "{found:?}");
+        assert!((found.depth_cents - 30.0).abs() < 5.0, "{found:?}");
+    }
+
+    #[test]
+    fn bucket_conversions() {
+        let freq_detector = FreqDetector::new(44100, 4096).unwrap();
+        let resolution = 44100.0 / 4096.0;
+        assert_eq!(freq_detector.bucket_resolution(), resolution);
+        assert_eq!(freq_detector.fft_bucket_to_freq(2.5), 2.5 * resolution);
+        assert_eq!(freq_detector.freq_to_bucket(1000.0), 93);
+        assert_eq!(freq_detector.freq_to_bucket(-5.0), 0);
+        assert_eq!(freq_detector.freq_to_bucket(1e9), 2048);
+        for bucket in [0, 1, 93, 2048] {
+            let freq = freq_detector.fft_bucket_to_freq(bucket as f32);
+            assert_eq!(freq_detector.freq_to_bucket(freq), bucket);
+        }
+    }
+
+    #[test]
+    fn complex_input_tells_negative_frequencies_apart() {
+        use rustfft::num_complex::Complex;
+        use std::f32::consts::TAU;
+        let sample_count = 4096;
+        let freq_detector =
+            FreqDetector::with_window(44100, sample_count, WindowFunction::Hann).unwrap();
+        for freq in [-3000.0, 5000.3, -20000.0] {
+            let samples = (0..sample_count)
+                .map(|i| Complex::from_polar(0.5, i as f32 / 44100.0 * freq * TAU))
+                .collect::<Vec<_>>();
+            let detected = freq_detector.detect_complex(&samples).unwrap();
+            assert!((detected - freq).abs() < 0.5, "{detected} vs {freq}");
+        }
+        let silence = vec![Complex::new(0.0, 0.0); sample_count];
+        assert_eq!(freq_detector.detect_complex(&silence).unwrap(), 0.0);
+    }
+
+    #[test]
+    fn complex_input_is_notched_on_both_sides() {
+        use rustfft::num_complex::Complex;
+        use std::f32::consts::TAU;
+        let sample_count = 4096;
+        let mut freq_detector =
+            FreqDetector::with_window(44100, sample_count, WindowFunction::Hann).unwrap();
+        // hum at minus 60 Hz, louder than the signal at 1000 Hz
+        let samples = (0..sample_count)
+            .map(|i| {
+                let t = i as f32 / 44100.0;
+                Complex::from_polar(1.0, -t * 60.0 * TAU)
+                    + Complex::from_polar(0.3, t * 1000.0 * TAU)
+            })
+            .collect::<Vec<_>>();
+        let detected = freq_detector.detect_complex(&samples).unwrap();
+        assert!((detected + 60.0).abs() < 0.5, "{detected}");
+
+        freq_detector.set_notch(Some(60.0)).unwrap();
+        let detected = freq_detector.detect_complex(&samples).unwrap();
+        assert!((detected - 1000.0).abs() < 0.5, "{detected}");
+    }
+
+    #[test]
+    fn complex_input_takes_the_noise_floor_from_the_whole_spectrum() {
+        use rustfft::num_complex::Complex;
+        use std::f32::consts::TAU;
+        let sample_count = 4096;
+        let bucket_width = 44100.0 / sample_count as f32;
+        let mut freq_detector = FreqDetector::new(44100, sample_count).unwrap();
+        freq_detector.set_min_snr_db(Some(6.0));
+        // ignored for complex input, but its buckets are where the spectrum starts
+        // at minus Nyquist once reordered
+        freq_detector.set_freq_range(100.0, 1000.0).unwrap();
+
+        // a tone in every bucket of the lowest negative frequencies, and a louder one
+        let tone = |bucket: f32, amplitude: f32| {
+            move |i: usize| {
+                Complex::from_polar(amplitude, i as f32 * bucket / sample_count as f32 * TAU)
+            }
+        };
+        let crowded = (1..100).map(|bucket| tone(-2048.0 + bucket as f32, 1.0));
+        let tones = crowded.chain([tone(500.0, 3.0)]).collect::<Vec<_>>();
+        let samples = (0..sample_count)
+            .map(|i| tones.iter().map(|tone| tone(i)).sum())
+            .collect::<Vec<Complex<f32>>>();
+
+        let detected = freq_detector.detect_complex(&samples).unwrap();
+        assert!((detected - 500.0 * bucket_width).abs() < 0.5, "{detected}");
+    }
+
+    #[test]
+    fn output_units() {
+        use std::f32::consts::TAU;
+        let samples = (0..4096)
+            .map(|i| (i as f32 / 44100.0 * 1000.0 * TAU).sin())
+            .collect::<Vec<f32>>();
+        let mut freq_detector =
+            FreqDetector::with_window(44100, 4096, WindowFunction::Hann).unwrap();
+        let hertz = freq_detector.detect(&samples).unwrap();
+
+        freq_detector.set_output_unit(OutputUnit::BucketIndex);
+        let bucket = freq_detector.detect(&samples).unwrap();
+        assert!(
+            (bucket - 1000.0 * 4096.0 / 44100.0).abs() < 0.01,
+            "{bucket}"
+        );
+        assert!((freq_detector.fft_bucket_to_freq(bucket) - hertz).abs() < 1e-3);
+
+        freq_detector.set_output_unit(OutputUnit::NormalizedCyclesPerSample);
+        let normalized = freq_detector.detect(&samples).unwrap();
+        assert!((normalized * 44100.0 - hertz).abs() < 1e-3, "{normalized}");
+
+        let cents = freq_detector
+            .detect_cents(&samples, 1000.0)
+            .unwrap()
+            .unwrap();
+        assert!(cents.abs() < 1.0, "{cents}");
+    }
+
+    #[test]
+    fn near_nyquist_is_flagged() {
+        use std::f32::consts::TAU;
+        let freq_detector = FreqDetector::with_window(8000, 1024, WindowFunction::Hann).unwrap();
+        let tone = |freq: f32| {
+            (0..1024)
+                .map(|i| (i as f32 / 8000.0 * freq * TAU).sin())
+                .collect::<Vec<f32>>()
+        };
+        assert!(
+            freq_detector
+                .detect_full(&tone(3950.0))
+                .unwrap()
+                .near_nyquist
+        );
+        assert!(
+            !freq_detector
+                .detect_full(&tone(3000.0))
+                .unwrap()
+                .near_nyquist
+        );
+        assert!(
+            !freq_detector
+                .detect_full(&[0.0; 1024])
+                .unwrap()
+                .near_nyquist
+        );
+    }
+
+    #[test]
+    fn noise_floor_subtraction_finds_tone_in_pink_noise() {
+        use std::f32::consts::TAU;
+        let sample_count = 4096;
+        let freq = 3000.0;
+        let mut freq_detector =
+            FreqDetector::with_window(44100, sample_count, WindowFunction::Hann).unwrap();
+
+        let (mut plain_hits, mut subtracted_hits) = (0, 0);
+        for seed in 1..=20u64 {
+            let mut state = seed;
+            let mut pink = [0.0f32; 3];
+            let samples = (0..sample_count)
+                .map(|i| {
+                    state = state
+                        .wrapping_mul(6364136223846793005)
+                        .wrapping_add(1442695040888963407);
+                    let white = (state >> 40) as f32 / (1u64 << 24) as f32 - 0.5;
+                    pink[0] = 0.99765 * pink[0] + white * 0.0990460;
+                    pink[1] = 0.96300 * pink[1] + white * 0.2965164;
+                    pink[2] = 0.57000 * pink[2] + white * 1.0526913;
+                    let noise = pink.iter().sum::<f32>() + white * 0.1848;
+                    noise + 0.25 * (i as f32 / 44100.0 * freq * TAU).sin()
+                })
+                .collect::<Vec<f32>>();
+            let hit = |detected: f32| (detected - freq).abs() < 5.0;
+
+            freq_detector.set_noise_floor_width(None);
+            plain_hits += hit(freq_detector.detect(&samples).unwrap()) as usize;
+            freq_detector.set_noise_floor_width(Some(8));
+            subtracted_hits += hit(freq_detector.detect(&samples).unwrap()) as usize;
+        }
+        assert!(plain_hits <= 2, "{plain_hits}");
+        assert!(subtracted_hits >= 12, "{subtracted_hits}");
+    }
+
+    #[test]
+    fn detects_every_channel() {
+        use std::f32::consts::TAU;
+        let sample_count = 4096;
+        let freqs = [440.0, 660.0, 1000.0];
+        let interleaved = (0..sample_count)
+            .flat_map(|i| freqs.map(|f| (i as f32 / 44100.0 * f * TAU).sin()))
+            .collect::<Vec<f32>>();
+        let freq_detector = FreqDetector::new(44100, sample_count).unwrap();
+
+        let detected = freq_detector.detect_channels(&interleaved, 3).unwrap();
+        assert_eq!(detected.len(), 3);
+        for (detected, expected) in detected.iter().zip(freqs) {
+            assert!(
+                (detected - expected).abs() < 0.5,
+                "{detected} != {expected}"
+            );
+        }
+        assert!(matches!(
+            freq_detector.detect_channels(&interleaved, 2),
+            Err(DetectError::SampleCountMismatch {
+                expected: 8192,
+                passed: 12288
+            })
+        ));
+        assert!(matches!(
+            freq_detector.detect_channels(&interleaved, 0),
+            Err(DetectError::NoChannels)
+        ));
+        assert!(matches!(
+            freq_detector.detect_channels(&interleaved[1..], 3),
+            Err(DetectError::IncompleteFrame {
+                passed: 12287,
+                channels: 3
+            })
+        ));
+    }
+
+    #[test]
+    fn mixes_or_selects_interleaved_channels() {
+        use std::f32::consts::TAU;
+        let sample_count = 4096;
+        // the right channel is the left one with a louder tone on top
+        let interleaved = (0..sample_count)
+            .flat_map(|i| {
+                let t = i as f32 / 44100.0;
+                let left = (t * 440.0 * TAU).sin();
+                [left, left + 1.5 * (t * 1000.0 * TAU).sin()]
+            })
+            .collect::<Vec<f32>>();
+        let freq_detector = FreqDetector::new(44100, sample_count).unwrap();
+        let detect = |mode| freq_detector.detect_interleaved(&interleaved, 2, mode);
+
+        let left = detect(ChannelMode::Select(0)).unwrap();
+        assert!(left.len() == 1 && (left[0] - 440.0).abs() < 0.5, "{left:?}");
+        // 440 Hz in both channels outweighs 1000 Hz in one of them
+        let mixed = detect(ChannelMode::Mix).unwrap();
+        assert!(
+            mixed.len() == 1 && (mixed[0] - 440.0).abs() < 0.5,
+            "{mixed:?}"
+        );
+        assert_eq!(
+            detect(ChannelMode::PerChannel).unwrap(),
+            freq_detector.detect_channels(&interleaved, 2).unwrap()
+        );
+        assert!(matches!(
+            detect(ChannelMode::Select(2)),
+            Err(DetectError::NoSuchChannel {
+                channel: 2,
+                channels: 2
+            })
+        ));
+        assert!(matches!(
+            freq_detector.detect_interleaved(&interleaved, 3, ChannelMode::Mix),
+            Err(DetectError::IncompleteFrame { .. })
+        ));
+        // same errors as detect_channels instead of panicking
+        assert!(matches!(
+            freq_detector.detect_interleaved(&interleaved, 0, ChannelMode::Mix),
+            Err(DetectError::NoChannels)
+        ));
+        assert!(matches!(
+            freq_detector.detect_interleaved(&interleaved[..4096], 2, ChannelMode::Mix),
+            Err(DetectError::SampleCountMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn peak_db_is_independent_of_sample_count() {
+        use std::f32::consts::TAU;
+        for sample_count in [1024, 4096, 16384] {
+            let samples = (0..sample_count)
+                .map(|i| 0.5 * (i as f32 / 44100.0 * 1234.5 * TAU).sin())
+                .collect::<Vec<f32>>();
+            let freq_detector =
+                FreqDetector::with_window(44100, sample_count, WindowFunction::FlatTop).unwrap();
+            let (_, peak_db) = freq_detector.detect_peak_db(&samples).unwrap();
+            assert!((peak_db - -6.02).abs() < 0.05, "{sample_count}: {peak_db}");
+
+            let (_, silence_db) = freq_detector
+                .detect_peak_db(&vec![0.0; sample_count])
+                .unwrap();
+            assert_eq!(silence_db, -120.0);
+        }
+    }
+
+    #[test]
+    fn clones_can_be_shared_across_threads() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<FreqDetector>();
+        assert_send_sync::<FreqDetector<f64>>();
+
+        use std::f32::consts::TAU;
+        let sample_count = 4096;
+        let samples = (0..sample_count)
+            .map(|i| (i as f32 / 44100.0 * 440.0 * TAU).sin())
+            .collect::<Vec<f32>>();
+        let freq_detector = FreqDetector::builder(44100, sample_count)
+            .window(WindowFunction::Hann)
+            .build()
+            .unwrap();
+        let clone = freq_detector.clone();
+        assert!(Arc::ptr_eq(&freq_detector.fft, &clone.fft));
+
+        let expected = freq_detector.detect(&samples).unwrap();
+        std::thread::scope(|scope| {
+            for detector in [&freq_detector, &clone] {
+                scope.spawn(|| assert_eq!(detector.detect(&samples).unwrap(), expected));
+            }
+        });
+        assert_eq!(clone.config(), freq_detector.config());
+    }
+
+    #[test]
+    fn tie_between_buckets_is_reported_at_the_midpoint() {
+        use std::f32::consts::TAU;
+        let sample_count = 4096;
+        let freq = 100.5 * 44100.0 / sample_count as f32;
+        let samples = (0..sample_count)
+            .map(|i| (i as f32 / 44100.0 * freq * TAU).sin())
+            .collect::<Vec<f32>>();
+        for window in [WindowFunction::Rectangular, WindowFunction::Hann] {
+            let mut freq_detector = FreqDetector::with_window(44100, sample_count, window).unwrap();
+            let (low, high) = (
+                freq_detector.fft_bucket_to_freq(100.0),
+                freq_detector.fft_bucket_to_freq(101.0),
+            );
+            for interpolation in [
+                Interpolation::WeightedAverage,
+                Interpolation::Parabolic,
+                Interpolation::None,
+            ] {
+                freq_detector.set_interpolation(interpolation);
+                let detected = freq_detector.detect(&samples).unwrap();
+                assert!(
+                    low < detected && detected < high,
+                    "{window:?} {interpolation:?}: {detected}"
+                );
+                assert!((detected - freq).abs() < 0.01, "{detected} != {freq}");
+            }
+        }
+    }
+
+    #[test]
+    fn warm_up_allocates_the_buffers_of_detect_mut() {
+        use std::f32::consts::TAU;
+        let sample_count = 4096;
+        let samples = (0..sample_count)
+            .map(|i| (i as f32 / 44100.0 * 440.0 * TAU).sin())
+            .collect::<Vec<f32>>();
+        let freq_detector = FreqDetector::builder(44100, sample_count)
+            .window(WindowFunction::Hann)
+            .noise_floor_width(8)
+            .min_snr_db(6.0)
+            .build()
+            .unwrap();
+        // clones start without buffers
+        let mut freq_detector = freq_detector.clone();
+        freq_detector.warm_up();
+
+        let buffers = |detector: &FreqDetector| {
+            let scratch = &detector.scratch;
+            (
+                scratch.fft_input.as_ptr(),
+                scratch.fft_output.as_ptr(),
+                scratch.magnitudes.as_ptr(),
+                scratch.noise_floor.as_ptr(),
+                scratch.median_window.as_ptr(),
+                scratch.median_buffer.as_ptr(),
+            )
+        };
+        let warmed_up = buffers(&freq_detector);
+        let detected = freq_detector.detect_mut(&samples).unwrap();
+        assert!((detected - 440.0).abs() < 0.5, "{detected}");
+        assert_eq!(buffers(&freq_detector), warmed_up);
+    }
+
+    #[test]
+    fn yin_method_finds_fundamental_under_strong_harmonics() {
+        use std::f32::consts::TAU;
+        let sample_count = 2048;
+        // low E of a guitar, with the octave above much louder than the fundamental
+        let fundamental = 82.41;
+        let samples = (0..sample_count)
+            .map(|i| {
+                [0.3, 1.0, 0.5, 0.3]
+                    .iter()
+                    .enumerate()
+                    .map(|(h, amplitude)| {
+                        let freq = fundamental * (h + 1) as f32;
+                        amplitude * (i as f32 / 44100.0 * freq * TAU).sin()
+                    })
+                    .sum()
+            })
+            .collect::<Vec<f32>>();
+
+        let mut freq_detector =
+            FreqDetector::with_window(44100, sample_count, WindowFunction::Hann).unwrap();
+        let strongest = freq_detector.detect(&samples).unwrap();
+        assert!((strongest - 2.0 * fundamental).abs() < 5.0, "{strongest}");
+
+        let yin = freq_detector
+            .detect_with_method(&samples, DetectionMethod::Yin)
+            .unwrap();
+        assert!((yin - fundamental).abs() < 0.5, "{yin}");
+
+        freq_detector.set_output_unit(OutputUnit::BucketIndex);
+        let yin_bucket = freq_detector
+            .detect_with_method(&samples, DetectionMethod::Yin)
+            .unwrap();
+        assert!((yin_bucket - yin / freq_detector.bucket_resolution()).abs() < 1e-3);
+        assert_eq!(
+            freq_detector
+                .detect_with_method(&vec![0.0; sample_count], DetectionMethod::Yin)
+                .unwrap(),
+            0.0
+        );
+    }
+
+    #[test]
+    fn notch_removes_mains_hum() {
+        use std::f32::consts::TAU;
+        let sample_count = 4096;
+        let samples = (0..sample_count)
+            .map(|i| {
+                let t = i as f32 / 44100.0;
+                2.0 * (t * 50.0 * TAU).sin() + 0.5 * (t * 440.0 * TAU).sin()
+            })
+            .collect::<Vec<f32>>();
+        let mut freq_detector =
+            FreqDetector::with_window(44100, sample_count, WindowFunction::Hann).unwrap();
+        let hum = freq_detector.detect(&samples).unwrap();
+        assert!((hum - 50.0).abs() < 1.0, "{hum}");
+
+        freq_detector.set_notch(Some(50.0)).unwrap();
+        let tone = freq_detector.detect(&samples).unwrap();
+        assert!((tone - 440.0).abs() < 0.5, "{tone}");
+
+        for invalid in [0.0, 22050.0, f32::NAN] {
+            assert!(matches!(
+                freq_detector.set_notch(Some(invalid)),
+                Err(DetectorCreateError::InvalidTargetFreq)
+            ));
+        }
+    }
+
+    #[test]
+    fn snr_threshold_follows_the_signal_scale() {
+        use std::f32::consts::TAU;
+        let sample_count = 4096;
+        let mut seed = 1u32;
+        let noise = (0..sample_count)
+            .map(|_| {
+                seed = seed.wrapping_mul(1664525).wrapping_add(1013904223);
+                seed as f32 / u32::MAX as f32 - 0.5
+            })
+            .collect::<Vec<f32>>();
+        let tone = (0..sample_count)
+            .map(|i| 0.3 * (i as f32 / 44100.0 * 1000.0 * TAU).sin())
+            .collect::<Vec<f32>>();
+
+        let plain = FreqDetector::new(44100, sample_count).unwrap();
+        let snr = FreqDetector::builder(44100, sample_count)
+            .min_snr_db(20.0)
+            .build()
+            .unwrap();
+        for scale in [0.001, 1.0, 100.0] {
+            let noise = noise.iter().map(|n| n * scale).collect::<Vec<_>>();
+            assert!(matches!(
+                plain.detect_pitch(&noise),
+                Ok(Detection::Pitch(_))
+            ));
+            assert!(
+                matches!(snr.detect_pitch(&noise), Ok(Detection::Silent)),
+                "noise at {scale}"
+            );
+
+            let noisy_tone = noise
+                .iter()
+                .zip(&tone)
+                .map(|(n, t)| n + t * scale)
+                .collect::<Vec<_>>();
+            let freq = snr.detect(&noisy_tone).unwrap();
+            assert!((freq - 1000.0).abs() < 1.0, "tone at {scale}: {freq}");
+        }
+        assert_eq!(snr.config().min_snr_db, Some(20.0));
+    }
+
+    #[test]
+    fn silence_threshold_in_dbfs() {
+        use std::f32::consts::TAU;
+        // -50 dBFS
+        let amplitude = 10f32.powf(-50.0 / 20.0);
+        for (sample_count, window) in [
+            (1024, WindowFunction::Rectangular),
+            (8192, WindowFunction::Hann),
+        ] {
+            let samples = (0..sample_count)
+                .map(|i| amplitude * (i as f32 / 44100.0 * 1000.0 * TAU).sin())
+                .collect::<Vec<f32>>();
+            let mut freq_detector = FreqDetector::with_window(44100, sample_count, window).unwrap();
+
+            freq_detector.set_silence_threshold_dbfs(-40.0);
+            assert_eq!(
+                freq_detector.detect_pitch(&samples).unwrap(),
+                Detection::Silent
+            );
+            freq_detector.set_silence_threshold_dbfs(-60.0);
+            assert!(matches!(
+                freq_detector.detect_pitch(&samples).unwrap(),
+                Detection::Pitch(freq) if (freq - 1000.0).abs() < 1.0
+            ));
+        }
+    }
+
+    #[test]
+    fn cepstrum_finds_fundamental_of_formant_shaped_harmonics() {
+        use std::f32::consts::TAU;
+        let sample_count = 4096;
+        let fundamental = 150.0;
+        // a vowel-like formant around 700 Hz makes the 5th harmonic the loudest
+        let samples = (0..sample_count)
+            .map(|i| {
+                (1..=20)
+                    .map(|h| {
+                        let freq = fundamental * h as f32;
+                        let formant = (-((freq - 700.0) / 300.0).powi(2)).exp();
+                        (0.05 + formant) * (i as f32 / 44100.0 * freq * TAU).sin()
+                    })
+                    .sum()
+            })
+            .collect::<Vec<f32>>();
+
+        let freq_detector =
+            FreqDetector::with_window(44100, sample_count, WindowFunction::Hann).unwrap();
+        let strongest = freq_detector.detect(&samples).unwrap();
+        assert!((strongest - 5.0 * fundamental).abs() < 5.0, "{strongest}");
+
+        let cepstrum = freq_detector
+            .detect_with_method(&samples, DetectionMethod::Cepstrum)
+            .unwrap();
+        assert!((cepstrum - fundamental).abs() < 1.0, "{cepstrum}");
+        assert_eq!(
+            freq_detector
+                .detect_with_method(&vec![0.0; sample_count], DetectionMethod::Cepstrum)
+                .unwrap(),
+            0.0
+        );
     }
 }
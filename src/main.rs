@@ -0,0 +1,165 @@
+//! `freq-det analyze`: per-frame frequencies of a WAV file or raw PCM from stdin
+
+use std::{
+    fs,
+    io::{self, Read, Write},
+    process::ExitCode,
+};
+
+use clap::{value_parser, Arg, ArgMatches, Command};
+use freq_det::{freq_to_note, FreqDetector, TimedDetection, WavSamples};
+
+fn main() -> ExitCode {
+    let matches = command().get_matches();
+    let Some(("analyze", args)) = matches.subcommand() else {
+        unreachable!("the subcommand is required");
+    };
+    match analyze(args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("error: {error}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn command() -> Command {
+    Command::new("freq-det")
+        .about("Detects the most prominent frequency of audio")
+        .subcommand_required(true)
+        .subcommand(
+            Command::new("analyze")
+                .about("Prints the frequency, note and confidence of every frame")
+                .arg(
+                    Arg::new("input")
+                        .help("WAV file, or `-` for stdin. Stdin takes WAV or raw PCM")
+                        .default_value("-"),
+                )
+                .arg(
+                    Arg::new("sample-count")
+                        .long("sample-count")
+                        .help("Samples per frame")
+                        .value_parser(value_parser!(usize))
+                        .default_value("4096"),
+                )
+                .arg(
+                    Arg::new("hop")
+                        .long("hop")
+                        .help("Samples between the starts of frames")
+                        .value_parser(value_parser!(usize))
+                        .default_value("1024"),
+                )
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .value_parser(["text", "csv", "json"])
+                        .default_value("text"),
+                )
+                .arg(
+                    Arg::new("rate")
+                        .long("rate")
+                        .help("Sample rate of raw PCM")
+                        .value_parser(value_parser!(usize))
+                        .default_value("44100"),
+                )
+                .arg(
+                    Arg::new("pcm")
+                        .long("pcm")
+                        .help("Little-endian mono sample format of raw PCM")
+                        .value_parser(["s16", "f32"])
+                        .default_value("s16"),
+                ),
+        )
+}
+
+fn analyze(args: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let input = args.get_one::<String>("input").unwrap();
+    let bytes = if input == "-" {
+        let mut bytes = vec![];
+        io::stdin().read_to_end(&mut bytes)?;
+        bytes
+    } else {
+        fs::read(input)?
+    };
+    let (sample_rate, samples) = if bytes.starts_with(b"RIFF") {
+        let wav = WavSamples::parse(&bytes)?;
+        (wav.sample_rate, wav.samples)
+    } else {
+        let samples = match args.get_one::<String>("pcm").unwrap().as_str() {
+            "f32" => bytes
+                .chunks_exact(4)
+                .map(|b| f32::from_le_bytes(b.try_into().unwrap()))
+                .collect(),
+            _ => bytes
+                .chunks_exact(2)
+                .map(|b| i16::from_le_bytes([b[0], b[1]]) as f32 / 32768.0)
+                .collect(),
+        };
+        (*args.get_one::<usize>("rate").unwrap(), samples)
+    };
+
+    let sample_count = *args.get_one::<usize>("sample-count").unwrap();
+    let hop = *args.get_one::<usize>("hop").unwrap();
+    if hop == 0 {
+        return Err("hop must be at least 1 sample".into());
+    }
+    let detector = FreqDetector::new(sample_rate, sample_count)?;
+    let detections = detector.detect_all(&samples, hop)?;
+
+    let mut out = io::stdout().lock();
+    let format = args.get_one::<String>("format").unwrap().as_str();
+    match format {
+        "csv" => writeln!(out, "time,frequency,note,cents_off,confidence")?,
+        "json" => writeln!(out, "[")?,
+        _ => {}
+    }
+    for (i, detection) in detections.iter().enumerate() {
+        let separator = if i + 1 < detections.len() { "," } else { "" };
+        write_detection(&mut out, format, detection, separator)?;
+    }
+    if format == "json" {
+        writeln!(out, "]")?;
+    }
+    Ok(())
+}
+
+/// One line of output, `separator` follows JSON objects
+fn write_detection(
+    out: &mut impl Write,
+    format: &str,
+    detection: &TimedDetection,
+    separator: &str,
+) -> io::Result<()> {
+    let TimedDetection {
+        time,
+        frequency,
+        confidence,
+        ..
+    } = *detection;
+    let note = freq_to_note(frequency);
+    let name = note.map(|note| format!("{}{}", note.name, note.octave));
+    let cents_off = note.map(|note| note.cents_off);
+    match format {
+        "csv" => writeln!(
+            out,
+            "{time:.4},{frequency:.2},{},{},{confidence:.3}",
+            name.unwrap_or_default(),
+            cents_off.map_or(String::new(), |cents| format!("{cents:.0}")),
+        ),
+        "json" => writeln!(
+            out,
+            "  {{\"time\": {time:.4}, \"frequency\": {frequency:.2}, \"note\": {}, \
+             \"cents_off\": {}, \"confidence\": {confidence:.3}}}{separator}",
+            name.map_or("null".to_string(), |name| format!("\"{name}\"")),
+            cents_off.map_or("null".to_string(), |cents| format!("{cents:.0}")),
+        ),
+        _ => match note {
+            Some(note) => writeln!(
+                out,
+                "{time:9.3} s {frequency:9.2} Hz  {:<16} confidence {confidence:.2}",
+                note.to_string()
+            ),
+            None => writeln!(out, "{time:9.3} s  silence"),
+        },
+    }
+}
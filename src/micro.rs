@@ -0,0 +1,219 @@
+use alloc::{vec, vec::Vec};
+
+use microfft::{real::*, Complex32};
+
+use crate::{DetectError, DetectorCreateError};
+
+/// Longest real FFT of `microfft` with its default `size-4096` feature
+const MAX_SAMPLE_COUNT: usize = 4096;
+
+/// Strongest-peak detector on top of `microfft`, for `no_std` targets
+///
+/// Detects the same frequencies as a [crate::FreqDetector] with the default options,
+/// without the `std` ones. `microfft` only has FFTs of fixed sizes, so `sample_count`
+/// has to be a power of two from 4 to 4096. The float math comes from `libm`, and the
+/// buffer for the FFT is allocated once in [Self::new].
+///
+/// ```
+/// use freq_det::MicroFftDetector;
+///
+/// let mut detector = MicroFftDetector::new(8000, 512).unwrap();
+/// # let samples_from_adc = [0.0; 512];
+/// let freq = detector.detect(&samples_from_adc).unwrap();
+/// ```
+pub struct MicroFftDetector {
+    sample_rate: usize,
+    buffer: Vec<f32>,
+    silence_threshold: f32,
+}
+
+impl MicroFftDetector {
+    /// # Errors
+    /// - if sample rate is 0
+    /// - if fewer than 4 samples are passed
+    /// - if `sample_count` is not a power of two up to 4096
+    pub fn new(sample_rate: usize, sample_count: usize) -> Result<Self, DetectorCreateError> {
+        if sample_rate < 1 {
+            return Err(DetectorCreateError::SampleRateTooLow);
+        }
+        if sample_count < 4 {
+            return Err(DetectorCreateError::TooFewSamples);
+        }
+        if !sample_count.is_power_of_two() || sample_count > MAX_SAMPLE_COUNT {
+            return Err(DetectorCreateError::UnsupportedSampleCount);
+        }
+        Ok(Self {
+            sample_rate,
+            buffer: vec![0.0; sample_count],
+            silence_threshold: 0.0001,
+        })
+    }
+
+    pub fn sample_rate(&self) -> usize {
+        self.sample_rate
+    }
+
+    pub fn sample_count(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// See [crate::FreqDetector::set_silence_threshold], `0.0001` by default
+    pub fn set_silence_threshold(&mut self, threshold: f32) {
+        self.silence_threshold = threshold;
+    }
+
+    /// Frequency of the strongest peak, `0.0` for silence. Same as
+    /// [crate::FreqDetector::detect], but takes `&mut self` to reuse the FFT buffer.
+    ///
+    /// # Errors
+    ///
+    /// - if `samples.len()` does not match the `sample_count` passed to [Self::new]
+    /// - if there are `NaN`s or infinities in the sample slice
+    pub fn detect(&mut self, samples: &[f32]) -> Result<f32, DetectError> {
+        if samples.len() != self.buffer.len() {
+            return Err(DetectError::SampleCountMismatch {
+                expected: self.buffer.len(),
+                passed: samples.len(),
+            });
+        }
+        match samples.iter().find(|s| !s.is_finite()) {
+            Some(s) if s.is_nan() => return Err(DetectError::NansFound),
+            Some(_) => return Err(DetectError::NonFinite),
+            None => {}
+        }
+        self.buffer.copy_from_slice(samples);
+        let spectrum = rfft(&mut self.buffer);
+        // the Nyquist bucket is packed into the imaginary part of the DC one
+        let nyquist = libm::fabsf(spectrum[0].im);
+        spectrum[0].im = 0.0;
+        let magnitude = |bucket: usize| match spectrum.get(bucket) {
+            Some(c) => libm::sqrtf(c.re * c.re + c.im * c.im),
+            None => nyquist,
+        };
+
+        // the last of the strongest pairs of adjacent buckets, as the StrongestPicker
+        let (mut left, mut strongest) = (0, [magnitude(0), magnitude(1)]);
+        for bucket in 1..spectrum.len() {
+            let pair = [magnitude(bucket), magnitude(bucket + 1)];
+            if pair[0] + pair[1] >= strongest[0] + strongest[1] {
+                (left, strongest) = (bucket, pair);
+            }
+        }
+        let sum = strongest[0] + strongest[1];
+        if sum < self.silence_threshold {
+            return Ok(0.0);
+        }
+        // the weighted average is exact for the main lobe of the rectangular window
+        let offset = strongest[1] / sum;
+        let offset = if libm::fabsf(offset - 0.5) < 0.01 {
+            0.5
+        } else {
+            offset
+        };
+        Ok((left as f32 + offset) * self.sample_rate as f32 / self.buffer.len() as f32)
+    }
+}
+
+/// Runs the FFT of the length of `buffer` in place, see [microfft::real]
+fn rfft(buffer: &mut [f32]) -> &mut [Complex32] {
+    match buffer.len() {
+        4 => rfft_4(fixed(buffer)),
+        8 => rfft_8(fixed(buffer)),
+        16 => rfft_16(fixed(buffer)),
+        32 => rfft_32(fixed(buffer)),
+        64 => rfft_64(fixed(buffer)),
+        128 => rfft_128(fixed(buffer)),
+        256 => rfft_256(fixed(buffer)),
+        512 => rfft_512(fixed(buffer)),
+        1024 => rfft_1024(fixed(buffer)),
+        2048 => rfft_2048(fixed(buffer)),
+        4096 => rfft_4096(fixed(buffer)),
+        len => unreachable!("sample count {len} is checked in MicroFftDetector::new"),
+    }
+}
+
+fn fixed<const N: usize>(buffer: &mut [f32]) -> &mut [f32; N] {
+    buffer.try_into().expect("buffer to be as long as the FFT")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{DetectError, DetectorCreateError};
+
+    use super::MicroFftDetector;
+
+    fn tone(freq: f32, sample_count: usize) -> Vec<f32> {
+        use std::f32::consts::TAU;
+        (0..sample_count)
+            .map(|i| (i as f32 / 8000.0 * freq * TAU).sin())
+            .collect()
+    }
+
+    #[test]
+    fn detects_with_every_fft_size() {
+        for sample_count in [4096, 512, 64] {
+            let mut detector = MicroFftDetector::new(8000, sample_count).unwrap();
+            let freq = detector.detect(&tone(1000.0, sample_count)).unwrap();
+            assert!((freq - 1000.0).abs() < 0.5, "{sample_count}: {freq}");
+        }
+        let mut detector = MicroFftDetector::new(8000, 512).unwrap();
+        assert_eq!(detector.detect(&[0.0; 512]).unwrap(), 0.0);
+        detector.set_silence_threshold(1000.0);
+        assert_eq!(detector.detect(&tone(440.0, 512)).unwrap(), 0.0);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn detects_the_same_as_the_realfft_detector() {
+        for freq in [50.0, 440.0, 1234.5, 3999.0] {
+            let samples = tone(freq, 1024);
+            let detected = MicroFftDetector::new(8000, 1024)
+                .unwrap()
+                .detect(&samples)
+                .unwrap();
+            let expected = crate::FreqDetector::new(8000, 1024)
+                .unwrap()
+                .detect(&samples)
+                .unwrap();
+            assert!(
+                (detected - expected).abs() < 0.01,
+                "{detected} != {expected}"
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_unsupported_input() {
+        assert!(matches!(
+            MicroFftDetector::new(8000, 1000),
+            Err(DetectorCreateError::UnsupportedSampleCount)
+        ));
+        assert!(matches!(
+            MicroFftDetector::new(8000, 8192),
+            Err(DetectorCreateError::UnsupportedSampleCount)
+        ));
+        assert!(matches!(
+            MicroFftDetector::new(8000, 2),
+            Err(DetectorCreateError::TooFewSamples)
+        ));
+        let mut detector = MicroFftDetector::new(8000, 64).unwrap();
+        assert!(matches!(
+            detector.detect(&[0.0; 32]),
+            Err(DetectError::SampleCountMismatch {
+                expected: 64,
+                passed: 32
+            })
+        ));
+        let mut samples = [0.0; 64];
+        samples[3] = f32::NAN;
+        assert!(matches!(
+            detector.detect(&samples),
+            Err(DetectError::NansFound)
+        ));
+        samples[3] = f32::INFINITY;
+        assert!(matches!(
+            detector.detect(&samples),
+            Err(DetectError::NonFinite)
+        ));
+    }
+}
@@ -0,0 +1,182 @@
+use std::{
+    fs,
+    io::{self, Write},
+    path::Path,
+};
+
+use rustfft::num_traits::Float;
+
+use crate::NoteEvent;
+
+/// Ticks per quarter note of written files
+const TICKS_PER_QUARTER: u16 = 480;
+/// Tempo of written files, 120 bpm, so a second is 960 ticks
+const MICROSECONDS_PER_QUARTER: u32 = 500_000;
+const TICKS_PER_SECOND: f64 = TICKS_PER_QUARTER as f64 * 1e6 / MICROSECONDS_PER_QUARTER as f64;
+/// Detected notes carry no dynamics, they are all played with the same velocity
+pub const NOTE_VELOCITY: u8 = 100;
+
+/// Channel voice message for playing [NoteEvent]s
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MidiMessage {
+    NoteOn { key: u8, velocity: u8 },
+    NoteOff { key: u8 },
+}
+
+impl MidiMessage {
+    /// Bytes of the message on `channel`, `0`-`15`, e.g. for a live MIDI output port
+    pub fn to_bytes(self, channel: u8) -> [u8; 3] {
+        let channel = channel & 0x0F;
+        match self {
+            MidiMessage::NoteOn { key, velocity } => [0x90 | channel, key, velocity],
+            MidiMessage::NoteOff { key } => [0x80 | channel, key, 0],
+        }
+    }
+}
+
+/// Note on and note off messages of `notes` with their times in seconds, in the order
+/// they have to be sent. A note ending at the same time as the next one starts
+/// is released first.
+///
+/// ```
+/// use freq_det::{midi_messages, FreqDetector};
+///
+/// let detector = FreqDetector::new(44100, 2048).unwrap();
+/// # let melody = vec![0.0; 44100];
+/// let notes = detector.transcribe(&melody, 256, 0.3).unwrap();
+/// for (time, message) in midi_messages(&notes) {
+///     // wait until `time`, then send
+///     let bytes = message.to_bytes(0);
+/// }
+/// ```
+pub fn midi_messages<T: Float>(notes: &[NoteEvent<T>]) -> Vec<(T, MidiMessage)> {
+    let mut messages = notes
+        .iter()
+        .flat_map(|note| {
+            let key = note.midi_note.min(127);
+            [
+                (
+                    note.start,
+                    MidiMessage::NoteOn {
+                        key,
+                        velocity: NOTE_VELOCITY,
+                    },
+                ),
+                (note.start + note.duration, MidiMessage::NoteOff { key }),
+            ]
+        })
+        .collect::<Vec<_>>();
+    // releases come before attacks at the same time
+    messages.sort_by(|(a, a_message), (b, b_message)| {
+        let is_on = |message: &MidiMessage| matches!(message, MidiMessage::NoteOn { .. });
+        a.partial_cmp(b)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then(is_on(a_message).cmp(&is_on(b_message)))
+    });
+    messages
+}
+
+/// Writes `notes` as a standard MIDI file with a single track at 120 bpm on channel 0,
+/// to be imported into a DAW. Times are rounded to 1/960 of a second.
+///
+/// # Errors
+/// - if writing fails
+pub fn write_midi(notes: &[NoteEvent<impl Float>], mut writer: impl Write) -> io::Result<()> {
+    let mut track = vec![];
+    // tempo meta event
+    track.extend([0x00, 0xFF, 0x51, 0x03]);
+    track.extend(&MICROSECONDS_PER_QUARTER.to_be_bytes()[1..]);
+    let mut previous_tick = 0;
+    for (time, message) in midi_messages(notes) {
+        let tick = (time.to_f64().unwrap_or(0.0).max(0.0) * TICKS_PER_SECOND).round() as u32;
+        write_variable_length(&mut track, tick.saturating_sub(previous_tick));
+        previous_tick = previous_tick.max(tick);
+        track.extend(message.to_bytes(0));
+    }
+    // end of track meta event
+    track.extend([0x00, 0xFF, 0x2F, 0x00]);
+
+    writer.write_all(b"MThd")?;
+    writer.write_all(&6u32.to_be_bytes())?;
+    // format 0, one track
+    writer.write_all(&0u16.to_be_bytes())?;
+    writer.write_all(&1u16.to_be_bytes())?;
+    writer.write_all(&TICKS_PER_QUARTER.to_be_bytes())?;
+    writer.write_all(b"MTrk")?;
+    writer.write_all(&(track.len() as u32).to_be_bytes())?;
+    writer.write_all(&track)
+}
+
+/// Same as [write_midi], into a new file at `path`
+///
+/// # Errors
+/// - if the file cannot be created or written
+pub fn save_midi(notes: &[NoteEvent<impl Float>], path: impl AsRef<Path>) -> io::Result<()> {
+    let mut file = io::BufWriter::new(fs::File::create(path)?);
+    write_midi(notes, &mut file)?;
+    file.flush()
+}
+
+/// Delta time of an event, 7 bits per byte with the most significant first
+fn write_variable_length(bytes: &mut Vec<u8>, value: u32) {
+    let mut groups = vec![(value & 0x7F) as u8];
+    let mut rest = value >> 7;
+    while rest > 0 {
+        groups.push((rest & 0x7F) as u8 | 0x80);
+        rest >>= 7;
+    }
+    bytes.extend(groups.iter().rev());
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::NoteEvent;
+
+    use super::{midi_messages, write_midi, MidiMessage};
+
+    #[test]
+    fn writes_standard_midi_files() {
+        let note = |start: f32, midi_note| NoteEvent {
+            start,
+            duration: 0.25,
+            midi_note,
+            mean_freq: 0.0,
+        };
+        let mut bytes = vec![];
+        write_midi(&[note(0.5, 69), note(0.75, 69)], &mut bytes).unwrap();
+
+        let header = [b"MThd".as_slice(), &[0, 0, 0, 6, 0, 0, 0, 1, 0x01, 0xE0]].concat();
+        assert_eq!(bytes[..14], header);
+        let track = [
+            // tempo
+            [0x00, 0xFF, 0x51, 0x03, 0x07, 0xA1, 0x20].as_slice(),
+            // 480 ticks, A4 on
+            &[0x83, 0x60, 0x90, 69, 100],
+            // 240 ticks, A4 off and on again
+            &[0x81, 0x70, 0x80, 69, 0],
+            &[0x00, 0x90, 69, 100],
+            &[0x81, 0x70, 0x80, 69, 0],
+            &[0x00, 0xFF, 0x2F, 0x00],
+        ]
+        .concat();
+        assert_eq!(bytes[14..18], *b"MTrk");
+        assert_eq!(bytes[18..22], (track.len() as u32).to_be_bytes());
+        assert_eq!(bytes[22..], track);
+
+        let messages = midi_messages(&[note(0.0, 60)]);
+        assert_eq!(
+            messages,
+            [
+                (
+                    0.0,
+                    MidiMessage::NoteOn {
+                        key: 60,
+                        velocity: 100
+                    }
+                ),
+                (0.25, MidiMessage::NoteOff { key: 60 })
+            ]
+        );
+        assert_eq!(messages[1].1.to_bytes(3), [0x83, 60, 0]);
+    }
+}
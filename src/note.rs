@@ -0,0 +1,222 @@
+use std::fmt;
+
+/// Standard concert pitch of A4
+pub const A4_FREQ: f32 = 440.0;
+
+/// Name of a note in the 12-tone equal temperament
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum NoteName {
+    C,
+    CSharp,
+    D,
+    DSharp,
+    E,
+    F,
+    FSharp,
+    G,
+    GSharp,
+    A,
+    ASharp,
+    B,
+}
+
+impl NoteName {
+    const ALL: [NoteName; 12] = [
+        NoteName::C,
+        NoteName::CSharp,
+        NoteName::D,
+        NoteName::DSharp,
+        NoteName::E,
+        NoteName::F,
+        NoteName::FSharp,
+        NoteName::G,
+        NoteName::GSharp,
+        NoteName::A,
+        NoteName::ASharp,
+        NoteName::B,
+    ];
+}
+
+impl fmt::Display for NoteName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            NoteName::C => "C",
+            NoteName::CSharp => "C#",
+            NoteName::D => "D",
+            NoteName::DSharp => "D#",
+            NoteName::E => "E",
+            NoteName::F => "F",
+            NoteName::FSharp => "F#",
+            NoteName::G => "G",
+            NoteName::GSharp => "G#",
+            NoteName::A => "A",
+            NoteName::ASharp => "A#",
+            NoteName::B => "B",
+        };
+        f.write_str(name)
+    }
+}
+
+/// The closest note to a frequency
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Note {
+    pub name: NoteName,
+    /// Scientific pitch notation octave, A4 is the concert pitch
+    pub octave: i32,
+    /// How sharp (positive) or flat (negative) the frequency is
+    /// compared to the note, between `-50.0` and `50.0`
+    pub cents_off: f32,
+}
+
+impl Note {
+    /// Same as [freq_to_note], the closest note in equal temperament with A4 = 440 Hz
+    pub fn from_freq(freq: f32) -> Option<Self> {
+        freq_to_note(freq)
+    }
+
+    /// Same as [freq_to_note_with_reference], with a custom frequency of A4
+    pub fn from_freq_with_reference(freq: f32, a4_freq: f32) -> Option<Self> {
+        freq_to_note_with_reference(freq, a4_freq)
+    }
+
+    /// The note with MIDI number `midi_number`, exactly in tune. `60` is C4, `69` is A4
+    pub fn from_midi_number(midi_number: i32) -> Self {
+        Self {
+            name: NoteName::ALL[midi_number.rem_euclid(12) as usize],
+            octave: midi_number.div_euclid(12) - 1,
+            cents_off: 0.0,
+        }
+    }
+
+    /// MIDI number of the note, regardless of [Self::cents_off]. `60` is C4, `69` is A4
+    pub fn midi_number(&self) -> i32 {
+        // C-1 is MIDI note 0
+        (self.octave + 1) * 12 + self.name as i32
+    }
+
+    /// Frequency of the note in equal temperament with A4 = 440 Hz,
+    /// regardless of [Self::cents_off]
+    pub fn freq(&self) -> f32 {
+        self.freq_with_reference(A4_FREQ)
+    }
+
+    /// Same as [Self::freq], but with a custom frequency of A4
+    pub fn freq_with_reference(&self, a4_freq: f32) -> f32 {
+        a4_freq * 2f32.powf((self.midi_number() - 69) as f32 / 12.0)
+    }
+}
+
+impl fmt::Display for Note {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}{} {:+.0} cents",
+            self.name, self.octave, self.cents_off
+        )
+    }
+}
+
+/// Closest note in equal temperament with A4 = 440 Hz
+///
+/// Returns `None` for frequencies that are not positive, e.g. the `0.0`
+/// returned by [crate::FreqDetector::detect] for silence.
+pub fn freq_to_note(freq: f32) -> Option<Note> {
+    freq_to_note_with_reference(freq, A4_FREQ)
+}
+
+/// Same as [freq_to_note], but with a custom frequency of A4,
+/// e.g. `415.0` for Baroque tuning
+pub fn freq_to_note_with_reference(freq: f32, a4_freq: f32) -> Option<Note> {
+    if !(freq > 0.0 && freq.is_finite()) {
+        return None;
+    }
+    let semitones_from_a4 = 12.0 * (freq / a4_freq).log2();
+    let nearest = semitones_from_a4.round();
+    // A4 is MIDI note 69
+    Some(Note {
+        cents_off: (semitones_from_a4 - nearest) * 100.0,
+        ..Note::from_midi_number(69 + nearest as i32)
+    })
+}
+
+/// How many cents `detected` is above (positive) or below (negative) `target`
+///
+/// Returns `NaN` instead of an infinity if either frequency is not positive,
+/// e.g. for the `0.0` returned by [crate::FreqDetector::detect] for silence.
+pub fn cents_off(detected: f32, target: f32) -> f32 {
+    if !(detected > 0.0 && target > 0.0) {
+        return f32::NAN;
+    }
+    1200.0 * (detected / target).log2()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{cents_off, freq_to_note, freq_to_note_with_reference, Note, NoteName};
+
+    #[test]
+    fn notes_from_frequencies() {
+        let a4 = freq_to_note(440.0).unwrap();
+        assert_eq!((a4.name, a4.octave), (NoteName::A, 4));
+        assert!(a4.cents_off.abs() < 0.01);
+
+        let middle_c = freq_to_note(261.63).unwrap();
+        assert_eq!((middle_c.name, middle_c.octave), (NoteName::C, 4));
+
+        let low_e = freq_to_note(82.41).unwrap();
+        assert_eq!((low_e.name, low_e.octave), (NoteName::E, 2));
+
+        // a quarter of a semitone sharp
+        let sharp_a = freq_to_note(440.0 * 2f32.powf(0.25 / 12.0)).unwrap();
+        assert_eq!(sharp_a.name, NoteName::A);
+        assert!((sharp_a.cents_off - 25.0).abs() < 0.01);
+        assert_eq!(sharp_a.to_string(), "A4 +25 cents");
+
+        assert!(freq_to_note(0.0).is_none());
+    }
+
+    #[test]
+    fn notes_from_associated_functions() {
+        assert_eq!(Note::from_freq(261.63), freq_to_note(261.63));
+        assert_eq!(Note::from_freq(261.63).unwrap().midi_number(), 60);
+        assert!(Note::from_freq(-1.0).is_none());
+
+        let a4 = Note::from_freq_with_reference(442.0, 442.0).unwrap();
+        assert_eq!((a4.name, a4.octave), (NoteName::A, 4));
+        assert!(a4.cents_off.abs() < 0.01);
+    }
+
+    #[test]
+    fn baroque_reference() {
+        let a4 = freq_to_note_with_reference(415.0, 415.0).unwrap();
+        assert_eq!((a4.name, a4.octave), (NoteName::A, 4));
+        // modern A4 is a semitone above
+        let modern_a4 = freq_to_note_with_reference(440.0, 415.0).unwrap();
+        assert_eq!((modern_a4.name, modern_a4.octave), (NoteName::ASharp, 4));
+    }
+
+    #[test]
+    fn cents_between_frequencies() {
+        assert!((cents_off(880.0, 440.0) - 1200.0).abs() < 0.01);
+        assert!((cents_off(440.0, 466.16) + 100.0).abs() < 0.1);
+        assert!(cents_off(0.0, 440.0).is_nan());
+        assert!(cents_off(440.0, 0.0).is_nan());
+    }
+
+    #[test]
+    fn midi_numbers_and_frequencies() {
+        let middle_c = freq_to_note(261.63).unwrap();
+        assert_eq!(middle_c.midi_number(), 60);
+        assert!((middle_c.freq() - 261.63).abs() < 0.01);
+
+        for midi_number in [0, 21, 60, 69, 108, 127] {
+            let note = Note::from_midi_number(midi_number);
+            assert_eq!(note.midi_number(), midi_number);
+            let detected = freq_to_note(note.freq()).unwrap();
+            assert_eq!((detected.name, detected.octave), (note.name, note.octave));
+        }
+        assert_eq!(Note::from_midi_number(69).freq_with_reference(442.0), 442.0);
+        let low_a = Note::from_midi_number(21);
+        assert_eq!((low_a.name, low_a.octave), (NoteName::A, 0));
+    }
+}
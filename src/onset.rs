@@ -0,0 +1,261 @@
+use std::collections::VecDeque;
+
+use rustfft::{num_traits::Float, FftNum};
+
+use crate::{float, DetectorCreateError, FreqDetector, Scratch};
+
+/// Number of past frames the adaptive threshold is computed from
+const FLUX_HISTORY: usize = 16;
+/// How many times the flux has to exceed its recent average to count as an onset
+const THRESHOLD_FACTOR: f64 = 2.0;
+/// Share of the spectrum that has to be new to count as an onset
+const MIN_RELATIVE_FLUX: f64 = 0.1;
+
+/// Start of a new note or another transient, see [OnsetDetector::push]
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Onset<T = f32> {
+    /// Index of the first sample of the hop the onset was detected in,
+    /// counting from the first sample ever pushed
+    pub sample: usize,
+    /// [Self::sample] in seconds
+    pub time: T,
+    /// Spectral flux of the frame, compare against other onsets to tell accents apart
+    pub strength: T,
+}
+
+/// Detects note attacks in a stream of samples
+///
+/// Takes the spectrum of the last `sample_count` samples every `hop_size` samples, like
+/// [crate::StreamingDetector], and sums up how much every bucket grew since the previous
+/// frame. This spectral flux jumps when a new note starts, even one of the same pitch.
+/// An onset is reported when the flux makes up more than a tenth of the whole spectrum
+/// and twice its average share over the last 16 frames, then the flux has to drop
+/// below the threshold again before the next onset is reported. Onsets closer than
+/// [OnsetDetector::set_min_interval] to the previous one are dropped, e.g. the bow noise
+/// or the fret buzz following an attack.
+///
+/// The window, DC removal and other preprocessing of the wrapped [FreqDetector] apply,
+/// and frames whose spectrum sums up to less than its silence threshold are never onsets.
+/// Shorter hops give more precise timestamps.
+///
+/// ```
+/// use freq_det::{FreqDetector, OnsetDetector};
+///
+/// let detector = FreqDetector::new(44100, 1024).unwrap();
+/// let mut onsets = OnsetDetector::new(detector, 256).unwrap();
+/// // no faster than sixteenth notes at 180 bpm
+/// onsets.set_min_interval(0.08);
+/// # let chunk_from_mic = vec![0.0; 512];
+/// if let Some(onset) = onsets.push(&chunk_from_mic) {
+///     println!("note started at {} s", onset.time);
+/// }
+/// ```
+pub struct OnsetDetector<T: FftNum = f32> {
+    detector: FreqDetector<T>,
+    hop_size: usize,
+    buffer: VecDeque<T>,
+    since_detection: usize,
+    samples_pushed: usize,
+    previous: Vec<T>,
+    flux_history: VecDeque<T>,
+    armed: bool,
+    /// Minimum distance between onsets in samples
+    min_interval: usize,
+    last_onset: Option<usize>,
+    scratch: Scratch<T>,
+}
+
+impl<T: FftNum + Float> OnsetDetector<T> {
+    /// # Errors
+    /// - if `hop_size` is 0
+    pub fn new(detector: FreqDetector<T>, hop_size: usize) -> Result<Self, DetectorCreateError> {
+        if hop_size < 1 {
+            return Err(DetectorCreateError::HopSizeTooSmall);
+        }
+        Ok(Self {
+            buffer: VecDeque::with_capacity(detector.sample_count()),
+            scratch: Scratch::new(detector.fft.as_ref()),
+            detector,
+            hop_size,
+            since_detection: 0,
+            samples_pushed: 0,
+            previous: vec![],
+            flux_history: VecDeque::with_capacity(FLUX_HISTORY),
+            armed: true,
+            min_interval: 0,
+            last_onset: None,
+        })
+    }
+
+    /// Appends `samples` of any length to the ring buffer.
+    ///
+    /// Returns the most recent onset if at least one was detected while pushing,
+    /// the first frame is taken once the buffer is full. Windows containing `NaN`s
+    /// or infinities are skipped.
+    pub fn push(&mut self, samples: &[T]) -> Option<Onset<T>> {
+        let sample_count = self.detector.sample_count();
+        let mut latest = None;
+        for &sample in samples {
+            if self.buffer.len() == sample_count {
+                self.buffer.pop_front();
+            }
+            self.buffer.push_back(sample);
+            self.samples_pushed += 1;
+            self.since_detection += 1;
+
+            if self.buffer.len() == sample_count && self.since_detection >= self.hop_size {
+                self.since_detection = 0;
+                let Some(strength) = self.next_frame() else {
+                    continue;
+                };
+                let sample = self.samples_pushed - self.hop_size.min(sample_count);
+                if self
+                    .last_onset
+                    .is_some_and(|last| sample - last < self.min_interval)
+                {
+                    continue;
+                }
+                self.last_onset = Some(sample);
+                latest = Some(Onset {
+                    sample,
+                    time: float::<T>(sample as f64) / float(self.detector.sample_rate() as f64),
+                    strength,
+                });
+            }
+        }
+        latest
+    }
+
+    /// Drops onsets less than `seconds` after the previous reported one, `0.0` by default.
+    /// Negative values are treated as `0.0`.
+    pub fn set_min_interval(&mut self, seconds: T) {
+        let samples = seconds * float(self.detector.sample_rate() as f64);
+        self.min_interval = samples.round().to_usize().unwrap_or(0);
+    }
+
+    pub fn detector(&self) -> &FreqDetector<T> {
+        &self.detector
+    }
+
+    /// Computes the flux of the buffered frame, returns it if the frame is an onset
+    fn next_frame(&mut self) -> Option<T> {
+        let samples = self.buffer.make_contiguous().iter().copied();
+        self.detector
+            .fill_magnitudes(samples, &mut self.scratch)
+            .ok()?;
+        let magnitudes = &self.scratch.magnitudes;
+        let total = magnitudes.iter().fold(T::zero(), |sum, m| sum + *m);
+        let flux = if self.previous.is_empty() {
+            total
+        } else {
+            magnitudes
+                .iter()
+                .zip(&self.previous)
+                .fold(T::zero(), |sum, (m, previous)| {
+                    sum + (*m - *previous).max(T::zero())
+                })
+        };
+        self.previous.clear();
+        self.previous.extend_from_slice(magnitudes);
+        // relative to the whole spectrum, so that loud sustained notes with their
+        // small fluctuations do not look like attacks
+        let relative_flux = if total > T::zero() {
+            flux / total
+        } else {
+            T::zero()
+        };
+
+        let average = if self.flux_history.is_empty() {
+            T::zero()
+        } else {
+            self.flux_history.iter().fold(T::zero(), |sum, f| sum + *f)
+                / float(self.flux_history.len() as f64)
+        };
+        if self.flux_history.len() == FLUX_HISTORY {
+            self.flux_history.pop_front();
+        }
+        self.flux_history.push_back(relative_flux);
+
+        let above = relative_flux > average * float(THRESHOLD_FACTOR)
+            && relative_flux > float(MIN_RELATIVE_FLUX)
+            && total >= self.detector.silence_threshold;
+        let is_onset = above && self.armed;
+        self.armed = !above;
+        is_onset.then_some(flux)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{DetectorCreateError, FreqDetector, WindowFunction};
+
+    use super::OnsetDetector;
+
+    #[test]
+    fn detects_note_attacks() {
+        use std::f32::consts::TAU;
+        let attacks = [5000, 20000, 35000, 50000];
+        let freqs = [440.0, 660.0, 660.0, 330.0];
+        let samples = (0..60000)
+            .map(|i| match attacks.iter().rposition(|attack| *attack <= i) {
+                Some(note) => {
+                    let t = (i - attacks[note]) as f32 / 44100.0;
+                    0.8 * (-t * 6.0).exp() * (t * freqs[note] * TAU).sin()
+                }
+                None => 0.0,
+            })
+            .collect::<Vec<f32>>();
+
+        let hop_size = 256;
+        let detector = FreqDetector::with_window(44100, 1024, WindowFunction::Hann).unwrap();
+        let mut onset_detector = OnsetDetector::new(detector, hop_size).unwrap();
+        let onsets = samples
+            .chunks(hop_size)
+            .filter_map(|chunk| onset_detector.push(chunk))
+            .collect::<Vec<_>>();
+
+        assert_eq!(onsets.len(), attacks.len(), "{onsets:?}");
+        for (onset, attack) in onsets.iter().zip(attacks) {
+            // the Hann window hides the newest samples, so it may take a few hops to notice
+            assert!(
+                (attack - hop_size..attack + 1024).contains(&onset.sample),
+                "{onset:?} for {attack}"
+            );
+            assert_eq!(onset.time, onset.sample as f32 / 44100.0);
+        }
+
+        assert!(matches!(
+            OnsetDetector::new(FreqDetector::<f32>::new(44100, 1024).unwrap(), 0),
+            Err(DetectorCreateError::HopSizeTooSmall)
+        ));
+    }
+
+    #[test]
+    fn drops_onsets_within_the_min_interval() {
+        use std::f32::consts::TAU;
+        let attacks = [5000, 10000, 20000];
+        let samples = (0..30000)
+            .map(|i| match attacks.iter().rposition(|attack| *attack <= i) {
+                Some(note) => {
+                    let t = (i - attacks[note]) as f32 / 44100.0;
+                    0.8 * (-t * 6.0).exp() * (t * 440.0 * (note + 1) as f32 * TAU).sin()
+                }
+                None => 0.0,
+            })
+            .collect::<Vec<f32>>();
+        let count_onsets = |min_interval: f32| {
+            let detector = FreqDetector::with_window(44100, 1024, WindowFunction::Hann).unwrap();
+            let mut onset_detector = OnsetDetector::new(detector, 256).unwrap();
+            onset_detector.set_min_interval(min_interval);
+            samples
+                .chunks(256)
+                .filter_map(|chunk| onset_detector.push(chunk))
+                .count()
+        };
+
+        assert_eq!(count_onsets(0.0), 3);
+        // the second attack follows the first after 113 ms, the third after 340 ms
+        assert_eq!(count_onsets(0.2), 2);
+    }
+}
@@ -0,0 +1,151 @@
+use std::fmt::Debug;
+
+use rustfft::{num_traits::Float, FftNum};
+
+use crate::compare;
+
+/// Chooses the peak of the spectrum that is reported as the detected frequency,
+/// see [crate::FreqDetectorBuilder::peak_picker]
+///
+/// The detector refines the frequency between the two adjacent buckets the picker
+/// chooses, and still reports silence if they are below its silence threshold.
+///
+/// ```
+/// use freq_det::{FreqDetector, PeakPicker};
+///
+/// /// Always the lowest pair of buckets that is not silent
+/// #[derive(Debug)]
+/// struct Lowest;
+///
+/// impl PeakPicker<f32> for Lowest {
+///     fn pick(&self, magnitudes: &[f32]) -> usize {
+///         magnitudes.windows(2).position(|w| w[0] + w[1] > 0.01).unwrap_or(0)
+///     }
+/// }
+///
+/// let detector = FreqDetector::builder(44100, 4096)
+///     .peak_picker(Lowest)
+///     .build()
+///     .unwrap();
+/// ```
+pub trait PeakPicker<T>: Debug + Send + Sync {
+    /// Index of the left bucket of the chosen pair, at most `magnitudes.len() - 2`.
+    ///
+    /// `magnitudes` are the buckets within the searched frequency range, after the
+    /// window, notch and noise floor subtraction, there are always at least 2 of them.
+    fn pick(&self, magnitudes: &[T]) -> usize;
+
+    /// Same as [Self::pick], with an empty `buffer` to sort magnitudes in, which is what
+    /// the detector calls. [crate::FreqDetector::detect_mut] and the other detections
+    /// reusing their buffers lend one with a capacity of the whole spectrum, so pickers
+    /// that need to sort do not allocate. Calls [Self::pick] by default.
+    fn pick_with_buffer(&self, magnitudes: &[T], buffer: &mut Vec<T>) -> usize {
+        let _ = buffer;
+        self.pick(magnitudes)
+    }
+}
+
+/// The pair of adjacent buckets with the biggest total magnitude, the default
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct StrongestPicker;
+
+impl<T: FftNum + Float> PeakPicker<T> for StrongestPicker {
+    fn pick(&self, magnitudes: &[T]) -> usize {
+        pair_sums(magnitudes)
+            .enumerate()
+            .max_by(|(_, s1), (_, s2)| compare(*s1, *s2))
+            .map_or(0, |(left, _)| left)
+    }
+}
+
+/// The lowest peak that is at least `ratio` times as strong as the strongest one.
+///
+/// Finds the fundamental of sounds with an overtone louder than the fundamental,
+/// as long as the fundamental is not much weaker, e.g. with a `ratio` of `0.3`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LowestStrongPicker<T = f32> {
+    pub ratio: T,
+}
+
+impl<T: FftNum + Float> PeakPicker<T> for LowestStrongPicker<T> {
+    fn pick(&self, magnitudes: &[T]) -> usize {
+        let strongest = StrongestPicker.pick(magnitudes);
+        let threshold = (magnitudes[strongest] + magnitudes[strongest + 1]) * self.ratio;
+        lowest_peak_above(magnitudes, threshold).unwrap_or(strongest)
+    }
+}
+
+/// The lowest peak that is at least `factor` times above the median of the searched
+/// spectrum, the strongest one if there is no such peak.
+///
+/// Unlike [LowestStrongPicker] the threshold does not depend on the loudest peak,
+/// so a quiet fundamental is found next to very strong overtones, as long as it
+/// stands out of the noise, e.g. with a `factor` of `10`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NoiseFloorPicker<T = f32> {
+    pub factor: T,
+}
+
+impl<T: FftNum + Float> PeakPicker<T> for NoiseFloorPicker<T> {
+    fn pick(&self, magnitudes: &[T]) -> usize {
+        self.pick_with_buffer(magnitudes, &mut Vec::with_capacity(magnitudes.len()))
+    }
+
+    fn pick_with_buffer(&self, magnitudes: &[T], buffer: &mut Vec<T>) -> usize {
+        buffer.clear();
+        buffer.extend(pair_sums(magnitudes));
+        let middle = buffer.len() / 2;
+        let (_, median, _) = buffer.select_nth_unstable_by(middle, |s1, s2| compare(*s1, *s2));
+        lowest_peak_above(magnitudes, *median * self.factor)
+            .unwrap_or_else(|| StrongestPicker.pick(magnitudes))
+    }
+}
+
+/// Total magnitude of every pair of adjacent buckets
+fn pair_sums<T: FftNum + Float>(magnitudes: &[T]) -> impl Iterator<Item = T> + '_ {
+    magnitudes.windows(2).map(|pair| pair[0] + pair[1])
+}
+
+/// Left bucket of the lowest pair that is a local maximum above `threshold`
+fn lowest_peak_above<T: FftNum + Float>(magnitudes: &[T], threshold: T) -> Option<usize> {
+    let pairs = magnitudes.len().saturating_sub(1);
+    let sum = |left: usize| magnitudes[left] + magnitudes[left + 1];
+    (0..pairs).find(|&left| {
+        let peak = sum(left);
+        peak >= threshold
+            && peak > T::zero()
+            && (left == 0 || peak >= sum(left - 1))
+            && (left + 1 == pairs || peak >= sum(left + 1))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LowestStrongPicker, NoiseFloorPicker, PeakPicker, StrongestPicker};
+
+    #[test]
+    fn built_in_pickers() {
+        // a weak fundamental at 2, a strong overtone at 6 over a noise floor of 0.1
+        let mut magnitudes = [0.1f32; 12];
+        magnitudes[2] = 1.0;
+        magnitudes[6] = 4.0;
+        magnitudes[7] = 2.0;
+
+        assert_eq!(StrongestPicker.pick(&magnitudes), 6);
+        assert_eq!(LowestStrongPicker { ratio: 0.5 }.pick(&magnitudes), 6);
+        // both pairs around a single peak sum up the same, the left one is lower
+        assert_eq!(LowestStrongPicker { ratio: 0.1 }.pick(&magnitudes), 1);
+        assert_eq!(NoiseFloorPicker { factor: 4.0 }.pick(&magnitudes), 1);
+        assert_eq!(NoiseFloorPicker { factor: 20.0 }.pick(&magnitudes), 6);
+
+        // a lent buffer is used without growing it
+        let mut buffer = Vec::with_capacity(magnitudes.len());
+        let picker = NoiseFloorPicker { factor: 4.0 };
+        assert_eq!(picker.pick_with_buffer(&magnitudes, &mut buffer), 1);
+        assert_eq!(buffer.capacity(), magnitudes.len());
+        assert_eq!(
+            StrongestPicker.pick_with_buffer(&magnitudes, &mut buffer),
+            6
+        );
+    }
+}
@@ -0,0 +1,91 @@
+// the wrappers generated by pyo3 convert the already converted errors again
+#![allow(clippy::useless_conversion)]
+
+use numpy::{PyArray1, PyReadonlyArray1};
+use pyo3::{exceptions::PyValueError, prelude::*};
+
+use crate::{DetectError, DetectorCreateError, FreqDetector, TimedDetection};
+
+impl From<DetectError> for PyErr {
+    fn from(error: DetectError) -> Self {
+        PyValueError::new_err(error.to_string())
+    }
+}
+
+impl From<DetectorCreateError> for PyErr {
+    fn from(error: DetectorCreateError) -> Self {
+        PyValueError::new_err(error.to_string())
+    }
+}
+
+/// Array of one field of every detection returned by [PyFreqDetector::detect_all]
+type Column<'py> = Bound<'py, PyArray1<f32>>;
+
+/// Python-facing wrapper around an `f32` [FreqDetector], `freq_det.FreqDetector`
+///
+/// Samples are 1-dimensional `numpy.float32` arrays, errors are raised as `ValueError`s.
+/// The GIL is released while detecting, so detectors can run on several Python threads.
+#[pyclass(name = "FreqDetector", module = "freq_det", frozen)]
+pub struct PyFreqDetector {
+    detector: FreqDetector<f32>,
+}
+
+#[pymethods]
+impl PyFreqDetector {
+    /// See [FreqDetector::new]
+    #[new]
+    pub fn new(sample_rate: usize, sample_count: usize) -> PyResult<Self> {
+        Ok(Self {
+            detector: FreqDetector::new(sample_rate, sample_count)?,
+        })
+    }
+
+    /// See [FreqDetector::detect]
+    pub fn detect(&self, py: Python<'_>, samples: PyReadonlyArray1<'_, f32>) -> PyResult<f32> {
+        let samples = samples.as_slice()?;
+        Ok(py.allow_threads(|| self.detector.detect(samples))?)
+    }
+
+    /// See [FreqDetector::detect_all], returns the arrays `(times, frequencies, confidences)`
+    /// with an element per window.
+    ///
+    /// # Errors
+    /// - if `hop_size` is `0`
+    /// - same as [FreqDetector::detect_all]
+    pub fn detect_all<'py>(
+        &self,
+        py: Python<'py>,
+        samples: PyReadonlyArray1<'py, f32>,
+        hop_size: usize,
+    ) -> PyResult<(Column<'py>, Column<'py>, Column<'py>)> {
+        if hop_size == 0 {
+            return Err(PyValueError::new_err("hop size must be at least 1 sample"));
+        }
+        let samples = samples.as_slice()?;
+        let detections = py.allow_threads(|| self.detector.detect_all(samples, hop_size))?;
+        let column = |field: fn(&TimedDetection) -> f32| {
+            PyArray1::from_iter_bound(py, detections.iter().map(field))
+        };
+        Ok((
+            column(|detection| detection.time),
+            column(|detection| detection.frequency),
+            column(|detection| detection.confidence),
+        ))
+    }
+
+    #[getter]
+    pub fn sample_rate(&self) -> usize {
+        self.detector.sample_rate()
+    }
+
+    #[getter]
+    pub fn sample_count(&self) -> usize {
+        self.detector.sample_count()
+    }
+}
+
+/// `import freq_det` of the extension module built with the `python` feature
+#[pymodule]
+fn freq_det(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyFreqDetector>()
+}
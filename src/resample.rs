@@ -0,0 +1,170 @@
+use std::f64::consts::PI;
+
+use rustfft::{num_traits::Float, FftNum};
+
+use crate::{float, DetectorCreateError};
+
+/// Zero crossings of the sinc kernel on each side of its center
+const ZERO_CROSSINGS: usize = 16;
+/// Kernel values tabulated per zero crossing, interpolated linearly in between
+const TABLE_RESOLUTION: usize = 256;
+/// Share of the lower Nyquist frequency kept by the low-pass filter,
+/// the rest is the transition band
+const CUTOFF: f64 = 0.95;
+
+/// Converts a stream of samples from one sample rate to another
+///
+/// Interpolates with a Blackman-windowed sinc kernel, which doubles as the low-pass
+/// filter removing everything above the lower of the two Nyquist frequencies, so
+/// decimating does not alias high frequencies down onto low pitches. The output lags
+/// about 17 samples of the lower sample rate behind the input.
+///
+/// ```
+/// use freq_det::Resampler;
+///
+/// let mut resampler = Resampler::<f32>::new(48000, 8000).unwrap();
+/// let mut decimated = vec![];
+/// # let chunk_from_mic = vec![0.0; 480];
+/// resampler.process(&chunk_from_mic, &mut decimated);
+/// ```
+pub struct Resampler<T = f32> {
+    /// Input samples per output sample
+    step: f64,
+    /// Scale of the kernel, below `1.0` when decimating
+    bandwidth: f64,
+    /// Half of the kernel in input samples
+    half_width: usize,
+    /// One side of the kernel, symmetric around `0`
+    kernel: Vec<T>,
+    /// Input samples still needed for the upcoming output samples
+    history: Vec<T>,
+    /// Position of the next output sample within `history`, in input samples
+    time: f64,
+}
+
+impl<T: FftNum + Float> Resampler<T> {
+    /// # Errors
+    /// - if either of the sample rates is `0`
+    pub fn new(from_rate: usize, to_rate: usize) -> Result<Self, DetectorCreateError> {
+        if from_rate == 0 || to_rate == 0 {
+            return Err(DetectorCreateError::SampleRateTooLow);
+        }
+        let bandwidth = (to_rate as f64 / from_rate as f64).min(1.0) * CUTOFF;
+        let half_width = (ZERO_CROSSINGS as f64 / bandwidth).ceil() as usize;
+        let kernel = (0..=ZERO_CROSSINGS * TABLE_RESOLUTION)
+            .map(|i| {
+                let x = i as f64 / TABLE_RESOLUTION as f64;
+                let sinc = if i == 0 {
+                    1.0
+                } else {
+                    (PI * x).sin() / (PI * x)
+                };
+                // Blackman window over the whole kernel, 1.0 at its center
+                let phase = PI * (x / ZERO_CROSSINGS as f64 + 1.0);
+                let window = 0.42 - 0.5 * phase.cos() + 0.08 * (2.0 * phase).cos();
+                float(bandwidth * sinc * window)
+            })
+            .collect();
+        Ok(Self {
+            step: from_rate as f64 / to_rate as f64,
+            bandwidth,
+            half_width,
+            kernel,
+            // silence before the first sample
+            history: vec![T::zero(); half_width],
+            time: half_width as f64,
+        })
+    }
+
+    /// Forgets the input so far, as if the resampler was just created
+    pub(crate) fn reset(&mut self) {
+        self.history.clear();
+        self.history.resize(self.half_width, T::zero());
+        self.time = self.half_width as f64;
+    }
+
+    /// Resamples `input` and appends the result to `output`.
+    ///
+    /// Can be called with chunks of any length, the samples are carried over so the
+    /// output is the same as for a single call with all of the input.
+    pub fn process(&mut self, input: &[T], output: &mut Vec<T>) {
+        self.history.extend_from_slice(input);
+        while (self.time.floor() as usize) + self.half_width < self.history.len() {
+            output.push(self.interpolate(self.time));
+            self.time += self.step;
+        }
+        let consumed = (self.time.floor() as usize).saturating_sub(self.half_width);
+        self.history.drain(..consumed.min(self.history.len()));
+        self.time -= consumed as f64;
+    }
+
+    /// Sample at `time` within `history`, all of the kernel has to be within `history`
+    fn interpolate(&self, time: f64) -> T {
+        let center = time.floor() as usize;
+        let first = center + 1 - self.half_width;
+        self.history[first..=center + self.half_width]
+            .iter()
+            .enumerate()
+            .fold(T::zero(), |sum, (i, sample)| {
+                let distance = ((first + i) as f64 - time).abs() * self.bandwidth;
+                sum + *sample * self.kernel_at(distance)
+            })
+    }
+
+    /// Kernel at `distance` zero crossings from its center
+    fn kernel_at(&self, distance: f64) -> T {
+        let position = distance * TABLE_RESOLUTION as f64;
+        let index = position.floor() as usize;
+        if index + 1 >= self.kernel.len() {
+            return T::zero();
+        }
+        let fraction = float::<T>(position - index as f64);
+        self.kernel[index] + (self.kernel[index + 1] - self.kernel[index]) * fraction
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::FreqDetector;
+
+    use super::Resampler;
+
+    #[test]
+    fn keeps_tones_and_drops_aliases() {
+        use std::f32::consts::TAU;
+        let tone = |freq: f32, rate: usize, len: usize| {
+            (0..len)
+                .map(|i| (i as f32 / rate as f32 * freq * TAU).sin())
+                .collect::<Vec<f32>>()
+        };
+
+        // in uneven chunks
+        let input = tone(440.0, 48000, 48000);
+        let mut resampler = Resampler::new(48000, 44100).unwrap();
+        let mut output = vec![];
+        for chunk in input.chunks(1000) {
+            resampler.process(chunk, &mut output);
+        }
+        let lag = output.len().abs_diff(44100);
+        assert!(lag <= 20, "{}", output.len());
+        let detector = FreqDetector::new(44100, 4096).unwrap();
+        let freq = detector.detect(&output[10000..14096]).unwrap();
+        assert!((freq - 440.0).abs() < 0.5, "{freq}");
+        // the amplitude survives, away from the start-up
+        let peak = output[1000..]
+            .iter()
+            .fold(0.0f32, |max, s| max.max(s.abs()));
+        assert!((peak - 1.0).abs() < 0.01, "{peak}");
+
+        // 7 kHz would alias to 1 kHz at 8 kHz
+        let mut decimator = Resampler::new(48000, 8000).unwrap();
+        let mut decimated = vec![];
+        decimator.process(&tone(7000.0, 48000, 48000), &mut decimated);
+        let leaked = decimated[1000..]
+            .iter()
+            .fold(0.0f32, |max, s| max.max(s.abs()));
+        assert!(leaked < 0.01, "{leaked}");
+
+        assert!(Resampler::<f32>::new(0, 8000).is_err());
+    }
+}
@@ -0,0 +1,201 @@
+use rustfft::{num_traits::Float, FftNum};
+
+use crate::{float, DetectError, FreqDetector, OnsetDetector};
+
+/// Semitones the pitch has to move away from a note to start the next one without
+/// an onset, more than half a semitone so vibrato does not split notes
+const PITCH_CHANGE: f64 = 0.75;
+/// Notes detected in fewer frames are dropped as glitches of transitions
+const MIN_NOTE_FRAMES: usize = 2;
+
+/// Note of a monophonic recording, see [FreqDetector::transcribe]
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NoteEvent<T = f32> {
+    /// Start of the note in seconds
+    pub start: T,
+    /// Length of the note in seconds
+    pub duration: T,
+    /// MIDI number of the nearest note to [Self::mean_freq], `69` is A4 at 440 Hz
+    pub midi_note: u8,
+    /// Average pitch of the note in Hz, whatever the [crate::OutputUnit] of the detector
+    pub mean_freq: T,
+}
+
+/// Collects the frames of the note being transcribed
+struct OpenNote<T> {
+    start: usize,
+    end: usize,
+    /// Semitone the note started at, relative to MIDI note `0`
+    semitone: T,
+    freq_sum: T,
+    frames: usize,
+}
+
+impl<T: FftNum + Float> FreqDetector<T> {
+    /// Splits a mono recording into notes, from the pitch of frames of `sample_count`
+    /// samples taken every `hop_size` samples and the attacks found by an
+    /// [OnsetDetector] with the same hop.
+    ///
+    /// Frames that are silent or have a confidence below `min_confidence` end the note,
+    /// see [crate::DetectionResult::confidence]. A new note starts at every onset, so
+    /// repeated notes of the same pitch are told apart, and when the pitch moves to
+    /// another note without an attack, e.g. in legato. Shorter hops give more precise
+    /// timing, the last note of a recording ends with its last whole frame.
+    ///
+    /// ```
+    /// use freq_det::{FreqDetector, WindowFunction};
+    ///
+    /// let detector = FreqDetector::with_window(44100, 2048, WindowFunction::Hann).unwrap();
+    /// # let melody = vec![0.0; 44100];
+    /// for note in detector.transcribe(&melody, 256, 0.3).unwrap() {
+    ///     println!("{:.2} s: MIDI {} for {:.2} s", note.start, note.midi_note, note.duration);
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Same as [Self::detect_all]
+    ///
+    /// # Panics
+    ///
+    /// - if `hop_size` is `0`
+    pub fn transcribe(
+        &self,
+        samples: &[T],
+        hop_size: usize,
+        min_confidence: T,
+    ) -> Result<Vec<NoteEvent<T>>, DetectError> {
+        let detections = self.detect_all(samples, hop_size)?;
+        let mut onset_detector =
+            OnsetDetector::new(self.clone(), hop_size).expect("hop size to be checked");
+        let onsets = samples
+            .chunks(hop_size)
+            .filter_map(|chunk| onset_detector.push(chunk))
+            .map(|onset| onset.sample)
+            .collect::<Vec<_>>();
+        let mut onsets = onsets.iter().copied().peekable();
+
+        let mut notes = vec![];
+        let mut open: Option<OpenNote<T>> = None;
+        for detection in detections {
+            // the pitch of a frame is the one around its center
+            let center = detection.sample + self.sample_count / 2;
+            let mut onset = None;
+            while let Some(sample) = onsets.next_if(|&sample| sample <= center) {
+                onset = Some(sample);
+            }
+
+            let voiced = detection.frequency > T::zero() && detection.confidence >= min_confidence;
+            if !voiced {
+                self.close_note(open.take(), &mut notes);
+                continue;
+            }
+            let freq = self.output_to_freq(detection.frequency);
+            let semitone = freq_to_semitone(freq);
+            let continues = open.as_ref().is_some_and(|note| {
+                // an onset noticed late belongs to a note the pitch change already started
+                let attacked = onset.is_some_and(|onset| note.start + self.sample_count <= onset);
+                !attacked && (semitone - note.semitone).abs() <= float(PITCH_CHANGE)
+            });
+            let end = center + hop_size / 2;
+            match open.as_mut() {
+                Some(note) if continues => {
+                    note.end = end;
+                    note.freq_sum = note.freq_sum + freq;
+                    note.frames += 1;
+                }
+                _ => {
+                    self.close_note(open.take(), &mut notes);
+                    open = Some(OpenNote {
+                        start: onset.unwrap_or(center.saturating_sub(hop_size / 2)),
+                        end,
+                        semitone: semitone.round(),
+                        freq_sum: freq,
+                        frames: 1,
+                    });
+                }
+            }
+        }
+        self.close_note(open, &mut notes);
+        Ok(notes)
+    }
+
+    fn close_note(&self, note: Option<OpenNote<T>>, notes: &mut Vec<NoteEvent<T>>) {
+        let Some(note) = note.filter(|note| note.frames >= MIN_NOTE_FRAMES) else {
+            return;
+        };
+        let mean_freq = note.freq_sum / float(note.frames as f64);
+        // notes outside of the MIDI range can not be reported
+        let Some(midi_note) = freq_to_semitone(mean_freq)
+            .round()
+            .to_u8()
+            .filter(|midi_note| *midi_note <= 127)
+        else {
+            return;
+        };
+        let sample_rate = float::<T>(self.sample_rate as f64);
+        notes.push(NoteEvent {
+            start: float::<T>(note.start as f64) / sample_rate,
+            duration: float::<T>(note.end.saturating_sub(note.start) as f64) / sample_rate,
+            midi_note,
+            mean_freq,
+        });
+    }
+}
+
+/// Semitones above MIDI note `0`, fractional for out-of-tune frequencies
+fn freq_to_semitone<T: FftNum + Float>(freq: T) -> T {
+    float::<T>(69.0) + float::<T>(12.0) * (freq / float(440.0)).log2()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{FreqDetector, OutputUnit, WindowFunction};
+
+    #[test]
+    fn transcribes_repeated_and_legato_notes() {
+        use std::f32::consts::TAU;
+        // A4, E5 twice and E4, then a glide-free legato step from E4 to G4
+        let attacks = [5000, 20000, 35000, 50000];
+        let freqs = [440.0, 659.26, 659.26, 329.63];
+        let legato_at = 62000;
+        let samples = (0..75000)
+            .map(|i| match attacks.iter().rposition(|attack| *attack <= i) {
+                Some(note) => {
+                    let t = (i - attacks[note]) as f32 / 44100.0;
+                    let freq = if i >= legato_at { 392.0 } else { freqs[note] };
+                    0.8 * (-t * 3.0).exp() * (t * freq * TAU).sin()
+                }
+                None => 0.0,
+            })
+            .collect::<Vec<f32>>();
+
+        let detector = FreqDetector::with_window(44100, 2048, WindowFunction::Hann).unwrap();
+        let notes = detector.transcribe(&samples, 256, 0.1).unwrap();
+        let midi_notes = notes.iter().map(|note| note.midi_note).collect::<Vec<_>>();
+        assert_eq!(midi_notes, [69, 76, 76, 64, 67], "{notes:?}");
+
+        let starts = [5000, 20000, 35000, 50000, legato_at];
+        for (note, start) in notes.iter().zip(starts) {
+            let start = start as f32 / 44100.0;
+            assert!((note.start - start).abs() < 0.03, "{note:?} for {start}");
+        }
+        // the repeated E5 ends where the next one starts
+        assert!((notes[1].start + notes[1].duration - notes[2].start).abs() < 0.03);
+        assert!((notes[0].mean_freq - 440.0).abs() < 1.0, "{:?}", notes[0]);
+
+        assert!(detector
+            .transcribe(&vec![0.0; 44100], 256, 0.1)
+            .unwrap()
+            .is_empty());
+
+        // notes are in Hz whatever the detector reports
+        let mut bucket_detector = detector.clone();
+        bucket_detector.set_output_unit(OutputUnit::BucketIndex);
+        assert_eq!(
+            bucket_detector.transcribe(&samples, 256, 0.1).unwrap(),
+            notes
+        );
+    }
+}
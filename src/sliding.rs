@@ -0,0 +1,251 @@
+use std::f64::consts::TAU;
+
+use rustfft::{num_complex::Complex, num_traits::Float, FftNum};
+
+use crate::{float, DetectorCreateError, FreqDetector, Scratch};
+
+/// Frequency tracking updated on every sample with a sliding DFT
+///
+/// Instead of an FFT over the whole window per detection, every bucket of the spectrum
+/// is rotated in place as a sample enters the window and the oldest one leaves, which
+/// costs `O(sample_count)` per sample. This pays off for hops of a few samples, beyond
+/// that [crate::StreamingDetector] is cheaper.
+///
+/// The window of the wrapped [FreqDetector] is applied in the frequency domain, which is
+/// exact as all of the [crate::WindowFunction]s are sums of cosines. So are its frequency
+/// range, peak picker, interpolation, output unit, DC removal, notch, noise floor
+/// subtraction and silence threshold. Pre-emphasis is not applied.
+///
+/// # Numerical stability
+///
+/// The recursive update is only marginally stable: rounding errors of every sample stay in
+/// the spectrum forever and pile up, and a `NaN` or infinity would never leave it. The
+/// spectrum is therefore recomputed from the buffered samples with a regular FFT every
+/// `sample_count` samples, which bounds the drift to the errors of a single window.
+///
+/// ```
+/// use freq_det::{FreqDetector, SlidingDetector};
+///
+/// let detector = FreqDetector::new(44100, 1024).unwrap();
+/// let mut sliding = SlidingDetector::new(detector).unwrap();
+/// # let samples_from_mic = vec![0.0; 16];
+/// for sample in samples_from_mic {
+///     let freq = sliding.push_sample(sample);
+/// }
+/// ```
+pub struct SlidingDetector<T: FftNum = f32> {
+    detector: FreqDetector<T>,
+    /// Ring buffer of the last `sample_count` samples, the oldest one at `position`
+    samples: Vec<T>,
+    position: usize,
+    since_resync: usize,
+    /// Spectrum of the samples without the window
+    spectrum: Vec<Complex<T>>,
+    /// Rotation of every bucket caused by shifting the window by one sample
+    twiddles: Vec<Complex<T>>,
+    /// How much of the neighboring buckets the window mixes into every bucket
+    window_kernel: Vec<T>,
+    scratch: Scratch<T>,
+}
+
+impl<T: FftNum + Float> SlidingDetector<T> {
+    /// Starts with a window of silence, so the first `sample_count` detections
+    /// look at a partially filled window.
+    ///
+    /// # Errors
+    /// - if `detector` zero-pads the samples
+    pub fn new(detector: FreqDetector<T>) -> Result<Self, DetectorCreateError> {
+        let sample_count = detector.sample_count();
+        if detector.fft.len() != sample_count {
+            return Err(DetectorCreateError::FftLengthMismatch);
+        }
+        let twiddles = (0..detector.fft.complex_len())
+            .map(|bucket| {
+                let phase = float(TAU * bucket as f64 / sample_count as f64);
+                Complex::from_polar(T::one(), phase)
+            })
+            .collect();
+        // w[n] = sum((-1)^k * a_k * cos(2pi * k * n / N)) turns into a convolution of the
+        // spectrum with a_0 at the bucket itself and (-1)^k * a_k / 2 at k buckets away
+        let window_kernel = detector
+            .window_function
+            .cosine_terms()
+            .iter()
+            .enumerate()
+            .map(|(k, a_k)| match k {
+                0 => float(*a_k),
+                k if k % 2 == 0 => float(a_k / 2.0),
+                _ => float(-a_k / 2.0),
+            })
+            .collect();
+        Ok(Self {
+            samples: vec![T::zero(); sample_count],
+            position: 0,
+            since_resync: 0,
+            spectrum: vec![Complex::new(T::zero(), T::zero()); detector.fft.complex_len()],
+            twiddles,
+            window_kernel,
+            scratch: Scratch::new(detector.fft.as_ref()),
+            detector,
+        })
+    }
+
+    /// Slides the window by one sample and returns the frequency detected in it,
+    /// `0.0` for silence as in [FreqDetector::detect].
+    pub fn push_sample(&mut self, sample: T) -> T {
+        let oldest = std::mem::replace(&mut self.samples[self.position], sample);
+        self.position = (self.position + 1) % self.samples.len();
+        self.since_resync += 1;
+        if self.since_resync == self.samples.len() {
+            self.since_resync = 0;
+            self.resync();
+        } else {
+            let change = sample - oldest;
+            for (bucket, twiddle) in self.spectrum.iter_mut().zip(&self.twiddles) {
+                *bucket = (*bucket + change) * twiddle;
+            }
+        }
+        self.fill_magnitudes();
+
+        let magnitudes = &self.scratch.magnitudes;
+        self.detector
+            .peak_window(magnitudes, &mut self.scratch.median_buffer)
+            .ok()
+            .and_then(|left| {
+                self.detector
+                    .refine_peak(magnitudes, left, &mut self.scratch.median_buffer)
+            })
+            .unwrap_or_else(T::zero)
+    }
+
+    pub fn detector(&self) -> &FreqDetector<T> {
+        &self.detector
+    }
+
+    /// Replaces the accumulated spectrum with an FFT of the buffered samples
+    fn resync(&mut self) {
+        let scratch = &mut self.scratch;
+        scratch.fft_input.clear();
+        scratch
+            .fft_input
+            .extend_from_slice(&self.samples[self.position..]);
+        scratch
+            .fft_input
+            .extend_from_slice(&self.samples[..self.position]);
+        self.detector
+            .fft
+            .process_with_scratch(
+                &mut scratch.fft_input,
+                &mut scratch.fft_output,
+                &mut scratch.fft_scratch,
+            )
+            .expect("buffers to be sized for the FFT");
+        self.spectrum.copy_from_slice(&scratch.fft_output);
+    }
+
+    /// Puts the magnitudes of the windowed spectrum into `scratch.magnitudes`
+    fn fill_magnitudes(&mut self) {
+        let sample_count = self.samples.len();
+        let spectrum = &self.spectrum;
+        // the negative frequencies mirror the positive ones of a real signal
+        let bucket = |index: isize| {
+            let index = index.rem_euclid(sample_count as isize) as usize;
+            if index < spectrum.len() {
+                spectrum[index]
+            } else {
+                spectrum[sample_count - index].conj()
+            }
+        };
+        // removing the mean of the samples removes this much of the window's own spectrum
+        let dc_offset = if self.detector.remove_dc {
+            spectrum[0]
+        } else {
+            Complex::new(T::zero(), T::zero())
+        };
+
+        self.scratch.magnitudes.clear();
+        for index in 0..spectrum.len() as isize {
+            let windowed = self.window_kernel.iter().enumerate().fold(
+                Complex::new(T::zero(), T::zero()),
+                |sum, (k, weight)| {
+                    let k = k as isize;
+                    let neighbors = if k == 0 {
+                        bucket(index)
+                    } else {
+                        bucket(index - k) + bucket(index + k)
+                    };
+                    let dc = if index == k {
+                        dc_offset
+                    } else {
+                        T::zero().into()
+                    };
+                    sum + (neighbors - dc) * *weight
+                },
+            );
+            self.scratch.magnitudes.push(windowed.norm());
+        }
+        self.detector.clean_up_magnitudes(&mut self.scratch);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{DetectorCreateError, FreqDetector, WindowFunction};
+
+    use super::SlidingDetector;
+
+    #[test]
+    fn matches_block_fft_over_a_long_run() {
+        use std::f32::consts::TAU;
+        let sample_count = 256;
+        // a slow sweep, long enough for a hundred resyncs
+        let mut phase = 0.0f32;
+        let samples = (0..sample_count * 100)
+            .map(|i| {
+                let freq = 300.0 + 2000.0 * i as f32 / (sample_count * 100) as f32;
+                phase += freq / 44100.0 * TAU;
+                0.5 * phase.sin() + 0.1
+            })
+            .collect::<Vec<f32>>();
+
+        for window in [WindowFunction::Rectangular, WindowFunction::BlackmanHarris] {
+            let block = FreqDetector::builder(44100, sample_count)
+                .window(window)
+                .remove_dc(true)
+                .build()
+                .unwrap();
+            let mut sliding = SlidingDetector::new(
+                FreqDetector::builder(44100, sample_count)
+                    .window(window)
+                    .remove_dc(true)
+                    .build()
+                    .unwrap(),
+            )
+            .unwrap();
+
+            for (i, sample) in samples.iter().enumerate() {
+                let freq = sliding.push_sample(*sample);
+                // checks every position relative to the resyncs sooner or later
+                if i >= sample_count && i % 97 == 0 {
+                    let expected = block.detect(&samples[i + 1 - sample_count..=i]).unwrap();
+                    assert!(
+                        (freq - expected).abs() < 0.05,
+                        "{window:?} at {i}: {freq} != {expected}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_zero_padding() {
+        let padded = FreqDetector::<f32>::builder(44100, 1000)
+            .zero_pad_to(1024)
+            .build()
+            .unwrap();
+        assert!(matches!(
+            SlidingDetector::new(padded),
+            Err(DetectorCreateError::FftLengthMismatch)
+        ));
+    }
+}
@@ -0,0 +1,191 @@
+use std::slice::ChunksExact;
+
+use rustfft::{num_traits::Float, FftNum};
+
+use crate::{amplitude_to_dbfs, float, DetectError, FreqDetector, Scratch};
+
+/// Unit of the values of a [Spectrogram]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SpectrogramScale {
+    /// Magnitudes of the buckets, as in [FreqDetector::spectrum]
+    #[default]
+    Magnitude,
+    /// Amplitude of a tone in the bucket in dBFS, as in [FreqDetector::detect_peak_db],
+    /// clamped to [crate::DBFS_FLOOR]
+    Dbfs,
+}
+
+/// Spectra of frames taken every `hop_size` samples, with their time and frequency axes
+///
+/// Values are stored frame by frame, one row of [Self::frequencies] per time in
+/// [Self::times]. The frames are what the detector searches for peaks: windowed, and
+/// with the notch and noise floor of the detector applied.
+///
+/// ```
+/// use freq_det::{FreqDetector, SpectrogramScale, WindowFunction};
+///
+/// let detector = FreqDetector::with_window(44100, 2048, WindowFunction::Hann).unwrap();
+/// # let recording = vec![0.0; 44100];
+/// // frames overlapping by three quarters
+/// let spectrogram = detector
+///     .spectrogram_full(&recording, 512, SpectrogramScale::Dbfs)
+///     .unwrap();
+/// for (time, frame) in spectrogram.times().iter().zip(spectrogram.frames()) {
+///     let loudest = frame.iter().copied().fold(f32::MIN, f32::max);
+///     println!("{time:.2} s: {loudest:.1} dBFS");
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Spectrogram<T = f32> {
+    scale: SpectrogramScale,
+    /// Start of every frame in seconds
+    times: Vec<T>,
+    /// Center of every bucket in Hz
+    frequencies: Vec<T>,
+    values: Vec<T>,
+}
+
+impl<T: Copy> Spectrogram<T> {
+    pub fn scale(&self) -> SpectrogramScale {
+        self.scale
+    }
+
+    /// Start of every frame in seconds
+    pub fn times(&self) -> &[T] {
+        &self.times
+    }
+
+    /// Center frequency of every bucket in Hz, from 0 Hz up to and including Nyquist
+    pub fn frequencies(&self) -> &[T] {
+        &self.frequencies
+    }
+
+    /// Number of frames
+    pub fn len(&self) -> usize {
+        self.times.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.times.is_empty()
+    }
+
+    /// Values of the frame at `index`, one per bucket
+    ///
+    /// # Panics
+    ///
+    /// - if `index` is not below [Self::len]
+    pub fn frame(&self, index: usize) -> &[T] {
+        let buckets = self.frequencies.len();
+        &self.values[index * buckets..(index + 1) * buckets]
+    }
+
+    pub fn frames(&self) -> ChunksExact<'_, T> {
+        self.values.chunks_exact(self.frequencies.len())
+    }
+
+    /// Value of `bucket` in the frame at `index`, `None` if either is out of range
+    pub fn get(&self, index: usize, bucket: usize) -> Option<T> {
+        if bucket >= self.frequencies.len() {
+            return None;
+        }
+        self.values
+            .get(index * self.frequencies.len() + bucket)
+            .copied()
+    }
+
+    /// All of the values, frame after frame, e.g. for drawing an image
+    pub fn as_slice(&self) -> &[T] {
+        &self.values
+    }
+}
+
+impl<T: FftNum + Float> FreqDetector<T> {
+    /// Same as [Self::spectrogram], but with the time and frequency axes and the values
+    /// in `scale`. The window function and the overlap of the frames are configured by
+    /// the detector and `hop_size`.
+    ///
+    /// # Errors
+    ///
+    /// - if there are `NaN`s or infinities in the sample slice
+    ///
+    /// # Panics
+    ///
+    /// - if `hop_size` is `0`
+    pub fn spectrogram_full(
+        &self,
+        samples: &[T],
+        hop_size: usize,
+        scale: SpectrogramScale,
+    ) -> Result<Spectrogram<T>, DetectError> {
+        assert!(hop_size > 0, "hop size must be at least 1 sample");
+        let mut scratch = Scratch::new(self.fft.as_ref());
+        let mut values = vec![];
+        let mut times = vec![];
+        // same as in detect_peak_db, so a full-scale tone reads 0 dBFS
+        let amplitude_scale = float::<T>(2.0 * self.window_function.amplitude_correction())
+            / float(self.sample_count as f64);
+        for (index, frame) in samples
+            .windows(self.sample_count)
+            .step_by(hop_size)
+            .enumerate()
+        {
+            self.fill_magnitudes(frame.iter().copied(), &mut scratch)?;
+            let frame = scratch.magnitudes.iter().map(|&magnitude| match scale {
+                SpectrogramScale::Magnitude => magnitude,
+                SpectrogramScale::Dbfs => amplitude_to_dbfs(magnitude * amplitude_scale),
+            });
+            values.extend(frame);
+            times.push(float::<T>((index * hop_size) as f64) / float(self.sample_rate as f64));
+        }
+        let frequencies = (0..self.fft.complex_len())
+            .map(|bucket| self.fft_bucket_to_freq(float(bucket as f64)))
+            .collect();
+        Ok(Spectrogram {
+            scale,
+            times,
+            frequencies,
+            values,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{FreqDetector, WindowFunction};
+
+    use super::SpectrogramScale;
+
+    #[test]
+    fn spectrogram_has_axes_and_scales() {
+        use std::f32::consts::TAU;
+        let sample_count = 1024;
+        let samples = (0..sample_count * 4)
+            .map(|i| (i as f32 / 8000.0 * 1000.0 * TAU).sin())
+            .collect::<Vec<f32>>();
+        let detector = FreqDetector::with_window(8000, sample_count, WindowFunction::Hann).unwrap();
+
+        let spectrogram = detector
+            .spectrogram_full(&samples, 256, SpectrogramScale::Dbfs)
+            .unwrap();
+        let columns = detector.spectrogram(&samples, 256).unwrap();
+        assert_eq!(spectrogram.len(), columns.len());
+        assert_eq!(spectrogram.times()[1], 256.0 / 8000.0);
+        assert_eq!(spectrogram.frequencies().len(), sample_count / 2 + 1);
+        assert_eq!(*spectrogram.frequencies().last().unwrap(), 4000.0);
+        assert_eq!(spectrogram.frames().count(), spectrogram.len());
+
+        // 1 kHz lands on bucket 128
+        let frame = spectrogram.frame(2);
+        assert!(frame[128].abs() < 0.01, "{}", frame[128]);
+        assert!(frame[300] < -60.0, "{}", frame[300]);
+        assert_eq!(spectrogram.get(2, 128), Some(frame[128]));
+        assert_eq!(spectrogram.get(2, sample_count), None);
+
+        let magnitudes = detector
+            .spectrogram_full(&samples, 256, SpectrogramScale::Magnitude)
+            .unwrap();
+        assert_eq!(magnitudes.frame(2), columns[2]);
+    }
+}
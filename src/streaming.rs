@@ -0,0 +1,426 @@
+use std::collections::VecDeque;
+
+use rustfft::{num_traits::Float, FftNum};
+
+use crate::{compare, Detection, DetectorCreateError, FreqDetector, Resampler, Scratch};
+
+/// Continuous frequency tracking over overlapping windows
+///
+/// Keeps the last `sample_count` samples in a ring buffer and runs
+/// the wrapped [FreqDetector] every `hop_size` samples. Like [FreqDetector::detect_mut],
+/// detections reuse buffers allocated up front, so pushing does not allocate
+/// once the first few detections are made.
+///
+/// ```
+/// use freq_det::{FreqDetector, StreamingDetector};
+///
+/// let detector = FreqDetector::new(44100, 4096).unwrap();
+/// // 50% overlap
+/// let mut streaming = StreamingDetector::new(detector, 2048).unwrap();
+/// # let chunk_from_mic = vec![0.0; 512];
+/// if let Some(freq) = streaming.push(&chunk_from_mic) {
+///     println!("{freq}");
+/// }
+/// ```
+pub struct StreamingDetector<T: FftNum = f32> {
+    detector: FreqDetector<T>,
+    hop_size: usize,
+    buffer: VecDeque<T>,
+    since_detection: usize,
+    smoothing: Option<T>,
+    median_frames: usize,
+    recent: VecDeque<T>,
+    sorted: Vec<T>,
+    smoothed: Option<T>,
+    resampler: Option<Resampler<T>>,
+    resampled: Vec<T>,
+    scratch: Scratch<T>,
+    /// Last pushed sample before the pre-emphasis filter
+    previous: T,
+}
+
+impl<T: FftNum + Float> StreamingDetector<T> {
+    /// # Errors
+    /// - if `hop_size` is 0
+    pub fn new(detector: FreqDetector<T>, hop_size: usize) -> Result<Self, DetectorCreateError> {
+        if hop_size < 1 {
+            return Err(DetectorCreateError::HopSizeTooSmall);
+        }
+        Ok(Self {
+            buffer: VecDeque::with_capacity(detector.sample_count()),
+            scratch: Scratch::new(detector.fft.as_ref()),
+            detector,
+            hop_size,
+            since_detection: 0,
+            smoothing: None,
+            median_frames: 1,
+            recent: VecDeque::new(),
+            sorted: vec![],
+            smoothed: None,
+            resampler: None,
+            resampled: vec![],
+            previous: T::zero(),
+        })
+    }
+
+    /// Applies exponential smoothing to the detected frequencies reported by
+    /// [Self::smoothed], off by default.
+    ///
+    /// Every detection moves the smoothed frequency by `factor` of the way towards it,
+    /// so `1.0` disables smoothing and smaller factors react slower. Silence resets
+    /// the smoothing, so the next note does not slide in from the previous one.
+    ///
+    /// # Errors
+    /// - if `factor` is not within `(0.0, 1.0]`
+    pub fn set_smoothing(&mut self, factor: Option<T>) -> Result<(), DetectorCreateError> {
+        if let Some(factor) = factor {
+            if !(factor > T::zero() && factor <= T::one()) {
+                return Err(DetectorCreateError::InvalidSmoothing);
+            }
+        }
+        self.smoothing = factor;
+        Ok(())
+    }
+
+    /// Takes the median of the last `frames` detections before the exponential smoothing
+    /// of [Self::set_smoothing], `1` by default, which disables the median.
+    ///
+    /// Unlike exponential smoothing, the median drops single outliers, such as an octave
+    /// jump, entirely instead of letting them drag the frequency along, at the cost of
+    /// lagging `frames / 2` detections behind. Silence resets the median as well.
+    pub fn set_median_frames(&mut self, frames: usize) {
+        self.median_frames = frames.max(1);
+        self.recent = VecDeque::with_capacity(self.median_frames);
+    }
+
+    /// Resamples the pushed samples from `rate` to the sample rate of the detector,
+    /// see [Resampler]. Off by default, `None` turns it off again.
+    ///
+    /// Lets a detector made for one rate take input of another, or track low pitches
+    /// cheaply with a detector for a low rate such as 8000 Hz. Samples already in the
+    /// buffer are kept.
+    ///
+    /// # Errors
+    /// - if `rate` is `0`
+    pub fn set_input_rate(&mut self, rate: Option<usize>) -> Result<(), DetectorCreateError> {
+        self.resampler = match rate {
+            Some(rate) if rate != self.detector.sample_rate() => {
+                Some(Resampler::new(rate, self.detector.sample_rate())?)
+            }
+            Some(_) | None => None,
+        };
+        Ok(())
+    }
+
+    /// Smoothed frequency, see [Self::set_smoothing] and [Self::set_median_frames].
+    ///
+    /// `None` before the first detection and after silence. Same as the raw
+    /// frequency returned by [Self::push] if smoothing is off.
+    pub fn smoothed(&self) -> Option<T> {
+        self.smoothed
+    }
+
+    /// Appends `samples` of any length to the ring buffer.
+    ///
+    /// Returns the most recent detection if at least one was made while pushing,
+    /// the first one is made once the buffer is full. Silence is reported as `0.0`,
+    /// as in [FreqDetector::detect], see [Self::smoothed] for the smoothed frequency.
+    /// Windows containing `NaN`s or infinities are skipped.
+    ///
+    /// The pre-emphasis filter of [FreqDetector::set_pre_emphasis] is applied to the
+    /// samples as they arrive, continuing from the last sample of the previous push,
+    /// so the detections do not depend on how the input is split into pushes.
+    pub fn push(&mut self, samples: &[T]) -> Option<T> {
+        let Some(resampler) = &mut self.resampler else {
+            return self.push_resampled(samples);
+        };
+        let mut resampled = std::mem::take(&mut self.resampled);
+        resampled.clear();
+        resampler.process(samples, &mut resampled);
+        let latest = self.push_resampled(&resampled);
+        self.resampled = resampled;
+        latest
+    }
+
+    /// [Self::push] of samples at the sample rate of the detector
+    fn push_resampled(&mut self, samples: &[T]) -> Option<T> {
+        let sample_count = self.detector.sample_count();
+        let mut latest = None;
+        for &sample in samples {
+            let sample = match self.detector.pre_emphasis {
+                Some(coefficient) => {
+                    let filtered = sample - coefficient * self.previous;
+                    self.previous = sample;
+                    filtered
+                }
+                None => sample,
+            };
+            if self.buffer.len() == sample_count {
+                self.buffer.pop_front();
+            }
+            self.buffer.push_back(sample);
+            self.since_detection += 1;
+
+            if self.buffer.len() == sample_count && self.since_detection >= self.hop_size {
+                self.since_detection = 0;
+                let window = self.buffer.make_contiguous();
+                match self
+                    .detector
+                    .detect_pitch_reusing(window, &mut self.scratch)
+                {
+                    Ok(Detection::Pitch(freq)) => {
+                        let median = self.median(freq);
+                        self.smoothed = Some(match (self.smoothed, self.smoothing) {
+                            (Some(previous), Some(factor)) => {
+                                previous + factor * (median - previous)
+                            }
+                            _ => median,
+                        });
+                        latest = Some(freq);
+                    }
+                    Ok(Detection::Silent) => {
+                        self.recent.clear();
+                        self.smoothed = None;
+                        latest = Some(T::zero());
+                    }
+                    Err(_) => {}
+                }
+            }
+        }
+        latest
+    }
+
+    /// Forgets the pushed samples and the smoothing, e.g. after a gap in the input.
+    ///
+    /// The next detection is made once the buffer is full of samples pushed afterwards,
+    /// so no window spans the gap.
+    pub fn reset(&mut self) {
+        self.buffer.clear();
+        self.since_detection = 0;
+        self.recent.clear();
+        self.smoothed = None;
+        self.previous = T::zero();
+        if let Some(resampler) = &mut self.resampler {
+            resampler.reset();
+        }
+    }
+
+    /// The detector passed to [Self::new]
+    pub fn detector(&self) -> &FreqDetector<T> {
+        &self.detector
+    }
+
+    /// Median of the last [Self::set_median_frames] detections, including `freq`
+    fn median(&mut self, freq: T) -> T {
+        if self.recent.len() == self.median_frames {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(freq);
+        self.sorted.clear();
+        self.sorted.extend(&self.recent);
+        let sorted = &mut self.sorted;
+        sorted.sort_unstable_by(|a, b| compare(*a, *b));
+        let middle = sorted.len() / 2;
+        if sorted.len().is_multiple_of(2) {
+            (sorted[middle - 1] + sorted[middle]) / (T::one() + T::one())
+        } else {
+            sorted[middle]
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{DetectorCreateError, FreqDetector};
+
+    use super::StreamingDetector;
+
+    #[test]
+    fn detects_every_hop() {
+        use std::f32::consts::TAU;
+        let sample_count = 4096;
+        let hop_size = 1024;
+        let samples = (0..sample_count * 4)
+            .map(|i| (i as f32 / 44100.0 * 440.0 * TAU).sin())
+            .collect::<Vec<_>>();
+
+        let detector = FreqDetector::new(44100, sample_count).unwrap();
+        let mut streaming = StreamingDetector::new(detector, hop_size).unwrap();
+
+        let fft_input = streaming.scratch.fft_input.as_ptr();
+        let detections = samples
+            .chunks(hop_size / 2)
+            .filter_map(|chunk| streaming.push(chunk))
+            .collect::<Vec<_>>();
+        // the buffers of the detection are reused for every hop
+        assert_eq!(streaming.scratch.fft_input.as_ptr(), fft_input);
+        assert_eq!(
+            detections.len(),
+            (samples.len() - sample_count) / hop_size + 1
+        );
+        for freq in detections {
+            assert!((freq - 440.0).abs() < 0.5, "detected {freq}");
+        }
+
+        assert!(matches!(
+            StreamingDetector::new(FreqDetector::<f32>::new(44100, 4096).unwrap(), 0),
+            Err(DetectorCreateError::HopSizeTooSmall)
+        ));
+    }
+
+    #[test]
+    fn pre_emphasis_carries_across_pushes() {
+        use std::f32::consts::TAU;
+        let sample_count = 2048;
+        let hop_size = 512;
+        let samples = (0..sample_count * 3)
+            .map(|i| {
+                let t = i as f32 / 44100.0;
+                (t * 200.0 * TAU).sin() + 0.3 * (t * 2000.0 * TAU).sin()
+            })
+            .collect::<Vec<_>>();
+        let mut detector = FreqDetector::new(44100, sample_count).unwrap();
+        detector.set_pre_emphasis(Some(0.97));
+
+        let mut unsplit = StreamingDetector::new(detector.clone(), hop_size).unwrap();
+        let unsplit = samples
+            .chunks(hop_size)
+            .filter_map(|chunk| unsplit.push(chunk))
+            .collect::<Vec<_>>();
+        let mut split = StreamingDetector::new(detector.clone(), hop_size).unwrap();
+        let split = samples
+            .chunks(37)
+            .filter_map(|chunk| split.push(chunk))
+            .collect::<Vec<_>>();
+        assert_eq!(split, unsplit);
+
+        // same as filtering the whole input at once
+        let filtered = (0..samples.len())
+            .map(|i| samples[i] - 0.97 * i.checked_sub(1).map_or(0.0, |i| samples[i]))
+            .collect::<Vec<_>>();
+        detector.set_pre_emphasis(None);
+        let expected = filtered
+            .windows(sample_count)
+            .step_by(hop_size)
+            .map(|window| detector.detect(window).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(unsplit, expected);
+        // the boosted overtone wins over the fundamental
+        assert!((unsplit[0] - 2000.0).abs() < 5.0, "{unsplit:?}");
+    }
+
+    #[test]
+    fn reset_waits_for_a_new_window() {
+        use std::f32::consts::TAU;
+        let sample_count = 2048;
+        let tone = |freq: f32| {
+            (0..sample_count)
+                .map(|i| (i as f32 / 44100.0 * freq * TAU).sin())
+                .collect::<Vec<_>>()
+        };
+        let detector = FreqDetector::new(44100, sample_count).unwrap();
+        let mut streaming = StreamingDetector::new(detector, 512).unwrap();
+        streaming.set_smoothing(Some(0.5)).unwrap();
+
+        assert!(streaming.push(&tone(400.0)).is_some());
+        streaming.reset();
+        assert_eq!(streaming.smoothed(), None);
+        // the old samples are gone, so a partial window does not detect
+        let next = tone(600.0);
+        let (start, end) = next.split_at(sample_count - 1);
+        assert_eq!(streaming.push(start), None);
+        let freq = streaming.push(end).unwrap();
+        assert!((freq - 600.0).abs() < 1.0, "{freq}");
+        assert_eq!(streaming.smoothed(), Some(freq));
+    }
+
+    #[test]
+    fn smoothing_resets_on_silence() {
+        use std::f32::consts::TAU;
+        let sample_count = 2048;
+        let tone = |freq: f32| {
+            (0..sample_count * 2)
+                .map(|i| (i as f32 / 44100.0 * freq * TAU).sin())
+                .collect::<Vec<_>>()
+        };
+        let detector = FreqDetector::new(44100, sample_count).unwrap();
+        let mut streaming = StreamingDetector::new(detector, sample_count).unwrap();
+        streaming.set_smoothing(Some(0.5)).unwrap();
+
+        streaming.push(&tone(400.0));
+        let settled = streaming.smoothed().unwrap();
+        assert!((settled - 400.0).abs() < 1.0, "{settled}");
+
+        let raw = streaming.push(&tone(500.0)[..sample_count]).unwrap();
+        assert!((raw - 500.0).abs() < 1.0, "{raw}");
+        let smoothed = streaming.smoothed().unwrap();
+        assert!((smoothed - 450.0).abs() < 1.0, "{smoothed}");
+
+        assert_eq!(streaming.push(&vec![0.0; sample_count]), Some(0.0));
+        assert_eq!(streaming.smoothed(), None);
+        streaming.push(&tone(600.0)[..sample_count]);
+        assert!((streaming.smoothed().unwrap() - 600.0).abs() < 1.0);
+
+        assert!(matches!(
+            streaming.set_smoothing(Some(0.0)),
+            Err(DetectorCreateError::InvalidSmoothing)
+        ));
+    }
+
+    #[test]
+    fn median_drops_outliers() {
+        use std::f32::consts::TAU;
+        let sample_count = 2048;
+        let tone = |freq: f32| {
+            (0..sample_count)
+                .map(|i| (i as f32 / 44100.0 * freq * TAU).sin())
+                .collect::<Vec<_>>()
+        };
+        let detector = FreqDetector::new(44100, sample_count).unwrap();
+        let mut streaming = StreamingDetector::new(detector, sample_count).unwrap();
+        streaming.set_median_frames(3);
+
+        for freq in [440.0, 442.0, 880.0, 441.0] {
+            streaming.push(&tone(freq));
+        }
+        // 880 is the median of no three consecutive detections
+        let smoothed = streaming.smoothed().unwrap();
+        assert!((smoothed - 442.0).abs() < 1.0, "{smoothed}");
+        streaming.push(&tone(440.0));
+        let smoothed = streaming.smoothed().unwrap();
+        assert!((smoothed - 441.0).abs() < 1.0, "{smoothed}");
+
+        streaming.push(&vec![0.0; sample_count]);
+        assert_eq!(streaming.smoothed(), None);
+        streaming.push(&tone(600.0));
+        assert!((streaming.smoothed().unwrap() - 600.0).abs() < 1.0);
+    }
+
+    #[test]
+    fn resamples_input_of_another_rate() {
+        use std::f32::consts::TAU;
+        // 48 kHz input with a tone that would alias down to 500 Hz at 8 kHz
+        let input = (0..48000)
+            .map(|i| {
+                let t = i as f32 / 48000.0;
+                (t * 110.0 * TAU).sin() + (t * 7500.0 * TAU).sin()
+            })
+            .collect::<Vec<f32>>();
+        let detector = FreqDetector::new(8000, 2048).unwrap();
+        let mut streaming = StreamingDetector::new(detector, 1024).unwrap();
+        streaming.set_input_rate(Some(48000)).unwrap();
+
+        let detections = input
+            .chunks(480)
+            .filter_map(|chunk| streaming.push(chunk))
+            .collect::<Vec<_>>();
+        // 8000 samples after resampling, less the start-up
+        assert_eq!(detections.len(), 6);
+        for freq in detections {
+            assert!((freq - 110.0).abs() < 1.0, "{freq}");
+        }
+
+        assert!(streaming.set_input_rate(Some(0)).is_err());
+        streaming.set_input_rate(None).unwrap();
+    }
+}
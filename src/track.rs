@@ -0,0 +1,198 @@
+use rustfft::{num_traits::Float, FftNum};
+
+use crate::{DetectError, FreqDetector, TimedDetection};
+
+/// One frame of a [PitchTrack]
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PitchFrame<T = f32> {
+    /// Start of the frame in seconds
+    pub time: T,
+    /// Pitch of the frame, `None` if it is unvoiced and was not interpolated
+    pub frequency: Option<T>,
+    /// The pitch was detected rather than interpolated, see [PitchTrack::interpolate_gaps]
+    pub voiced: bool,
+}
+
+/// Pitch contour over time, e.g. the intonation of speech
+///
+/// Frames where no pitch was detected are unvoiced. Short unvoiced gaps, such as
+/// consonants, can be bridged with [Self::interpolate_gaps] to get a continuous contour.
+///
+/// ```
+/// use freq_det::FreqDetector;
+///
+/// let detector = FreqDetector::new(16000, 1024).unwrap();
+/// # let speech = vec![0.0; 16000];
+/// let mut track = detector.pitch_track(&speech, 160, 0.5).unwrap();
+/// track.interpolate_gaps(0.1);
+/// for (time, freq) in track.to_vec() {
+///     println!("{time:.2} s: {freq:?}");
+/// }
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PitchTrack<T = f32> {
+    frames: Vec<PitchFrame<T>>,
+}
+
+impl<T: Float> PitchTrack<T> {
+    pub fn new() -> Self {
+        Self { frames: vec![] }
+    }
+
+    /// Frames with a frequency of `0.0`, i.e. silence, or a confidence below
+    /// `min_confidence` are unvoiced, see [crate::DetectionResult::confidence].
+    pub fn from_detections(detections: &[TimedDetection<T>], min_confidence: T) -> Self {
+        let mut track = Self::new();
+        for detection in detections {
+            let voiced = detection.frequency > T::zero() && detection.confidence >= min_confidence;
+            track.push(detection.time, voiced.then_some(detection.frequency));
+        }
+        track
+    }
+
+    /// Appends a frame, e.g. the output of [crate::StreamingDetector] with `None`
+    /// for silence. Frames are expected in chronological order.
+    pub fn push(&mut self, time: T, frequency: Option<T>) {
+        self.frames.push(PitchFrame {
+            time,
+            frequency,
+            voiced: frequency.is_some(),
+        });
+    }
+
+    pub fn frames(&self) -> &[PitchFrame<T>] {
+        &self.frames
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Fills the unvoiced frames between two frames with a pitch at most `max_gap`
+    /// seconds apart.
+    ///
+    /// The pitch changes linearly in cents over the gap, the frames stay unvoiced.
+    /// Gaps at the start and end of the track and longer pauses are left alone.
+    pub fn interpolate_gaps(&mut self, max_gap: T) {
+        let mut previous: Option<usize> = None;
+        for next in 0..self.frames.len() {
+            if self.frames[next].frequency.is_none() {
+                continue;
+            }
+            if let Some(previous) = previous.filter(|previous| next > previous + 1) {
+                let (start, end) = (self.frames[previous], self.frames[next]);
+                let (Some(start_freq), Some(end_freq)) = (start.frequency, end.frequency) else {
+                    unreachable!("gap ends are voiced");
+                };
+                if end.time - start.time <= max_gap && end.time > start.time {
+                    let ratio = end_freq / start_freq;
+                    for frame in &mut self.frames[previous + 1..next] {
+                        let position = (frame.time - start.time) / (end.time - start.time);
+                        frame.frequency = Some(start_freq * ratio.powf(position));
+                    }
+                }
+            }
+            previous = Some(next);
+        }
+    }
+
+    /// `(time, frequency)` of every frame, `None` for unvoiced ones that were not interpolated
+    pub fn to_vec(&self) -> Vec<(T, Option<T>)> {
+        self.frames
+            .iter()
+            .map(|frame| (frame.time, frame.frequency))
+            .collect()
+    }
+}
+
+impl<T: FftNum + Float> FreqDetector<T> {
+    /// Pitch contour of windows taken every `hop_size` samples, see [Self::detect_all]
+    /// and [PitchTrack::from_detections].
+    ///
+    /// # Errors
+    ///
+    /// Same as [Self::detect_all]
+    ///
+    /// # Panics
+    ///
+    /// - if `hop_size` is `0`
+    pub fn pitch_track(
+        &self,
+        samples: &[T],
+        hop_size: usize,
+        min_confidence: T,
+    ) -> Result<PitchTrack<T>, DetectError> {
+        let detections = self.detect_all(samples, hop_size)?;
+        Ok(PitchTrack::from_detections(&detections, min_confidence))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::FreqDetector;
+
+    use super::PitchTrack;
+
+    #[test]
+    fn interpolates_short_gaps_only() {
+        let mut track = PitchTrack::new();
+        let freqs = [
+            None,
+            Some(100.0),
+            None,
+            Some(400.0),
+            Some(400.0),
+            None,
+            None,
+            None,
+            Some(200.0),
+            None,
+        ];
+        for (i, freq) in freqs.into_iter().enumerate() {
+            track.push(i as f32 * 0.01, freq);
+        }
+        track.interpolate_gaps(0.025);
+
+        let filled = track.to_vec();
+        assert_eq!(filled[0], (0.0, None));
+        // halfway between 100 and 400 Hz in cents
+        assert!((filled[2].1.unwrap() - 200.0).abs() < 0.01, "{filled:?}");
+        assert!(!track.frames()[2].voiced);
+        // 40 ms between the voiced frames is too long
+        assert!(filled[5..8].iter().all(|(_, freq)| freq.is_none()));
+        assert_eq!(filled[9].1, None);
+
+        track.interpolate_gaps(0.05);
+        assert!((track.to_vec()[6].1.unwrap() - 282.84).abs() < 0.1);
+    }
+
+    #[test]
+    fn tracks_pitch_of_a_signal() {
+        use std::f32::consts::TAU;
+        // 220 Hz, a pause, 330 Hz
+        let samples = (0..16000)
+            .map(|i| match i {
+                0..6000 => (i as f32 / 16000.0 * 220.0 * TAU).sin(),
+                6000..10000 => 0.0,
+                _ => (i as f32 / 16000.0 * 330.0 * TAU).sin(),
+            })
+            .collect::<Vec<f32>>();
+        let detector = FreqDetector::new(16000, 1024).unwrap();
+        let track = detector.pitch_track(&samples, 500, 0.5).unwrap();
+
+        let frames = track.frames();
+        assert_eq!(frames.len(), (16000 - 1024) / 500 + 1);
+        assert!(
+            (frames[0].frequency.unwrap() - 220.0).abs() < 2.0,
+            "{frames:?}"
+        );
+        assert!(frames[15].frequency.is_none());
+        assert!((frames.last().unwrap().frequency.unwrap() - 330.0).abs() < 2.0);
+    }
+}
@@ -0,0 +1,163 @@
+use crate::{cents_off, DetectError, DetectionMethod, DetectorCreateError, FreqDetector};
+
+/// Which string is being played and how far it is from its target pitch
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TunerReading {
+    /// Index of the closest string in the tuning passed to [Tuner::new]
+    pub string: usize,
+    /// Target frequency of the string
+    pub target: f32,
+    /// Detected frequency in Hz, whatever the [crate::OutputUnit] of the detector
+    pub freq: f32,
+    /// How sharp (positive) or flat (negative) the string is
+    pub cents_off: f32,
+}
+
+/// Instrument tuner matching the detected pitch to the closest string of a tuning
+///
+/// ```
+/// use freq_det::{FreqDetector, Tuner};
+///
+/// let detector = FreqDetector::new(44100, 4096).unwrap();
+/// let tuner = Tuner::new(detector, &Tuner::GUITAR_STANDARD).unwrap();
+/// # let samples = vec![0.0; 4096];
+/// if let Some(reading) = tuner.tune(&samples).unwrap() {
+///     println!("string {}: {:+.0} cents", reading.string + 1, reading.cents_off);
+/// }
+/// ```
+pub struct Tuner {
+    detector: FreqDetector,
+    strings: Vec<f32>,
+    method: DetectionMethod,
+}
+
+impl Tuner {
+    /// E2 A2 D3 G3 B3 E4, from the lowest string
+    pub const GUITAR_STANDARD: [f32; 6] = [82.41, 110.0, 146.83, 196.0, 246.94, 329.63];
+    /// D2 A2 D3 G3 B3 E4, from the lowest string
+    pub const GUITAR_DROP_D: [f32; 6] = [73.42, 110.0, 146.83, 196.0, 246.94, 329.63];
+    /// E1 A1 D2 G2, from the lowest string
+    pub const BASS_STANDARD: [f32; 4] = [41.2, 55.0, 73.42, 98.0];
+
+    /// `strings` are the target frequencies, in any order. [Note::freq](crate::Note::freq)
+    /// gives the frequencies of custom tunings.
+    ///
+    /// # Errors
+    /// - if `strings` is empty
+    /// - if any of the `strings` is not between `0` and the Nyquist frequency
+    pub fn new(detector: FreqDetector, strings: &[f32]) -> Result<Self, DetectorCreateError> {
+        let nyquist = detector.sample_rate() as f32 / 2.0;
+        if strings.is_empty() || !strings.iter().all(|f| *f > 0.0 && *f < nyquist) {
+            return Err(DetectorCreateError::InvalidTargetFreq);
+        }
+        Ok(Self {
+            detector,
+            strings: strings.to_vec(),
+            method: DetectionMethod::Yin,
+        })
+    }
+
+    /// Changes how the pitch is detected, [DetectionMethod::Yin] by default,
+    /// as plucked strings often have harmonics louder than the fundamental.
+    pub fn set_method(&mut self, method: DetectionMethod) {
+        self.method = method;
+    }
+
+    /// Returns `None` if no pitch was detected, e.g. for silence
+    ///
+    /// # Errors
+    ///
+    /// Same as [FreqDetector::detect]
+    pub fn tune(&self, samples: &[f32]) -> Result<Option<TunerReading>, DetectError> {
+        let detected = self.detector.detect_with_method(samples, self.method)?;
+        if detected <= 0.0 {
+            return Ok(None);
+        }
+        let freq = self.detector.output_to_freq(detected);
+        Ok(self
+            .strings
+            .iter()
+            .map(|target| cents_off(freq, *target))
+            .enumerate()
+            .min_by(|(_, c1), (_, c2)| c1.abs().total_cmp(&c2.abs()))
+            .map(|(string, cents_off)| TunerReading {
+                string,
+                target: self.strings[string],
+                freq,
+                cents_off,
+            }))
+    }
+
+    /// The detector passed to [Self::new]
+    pub fn detector(&self) -> &FreqDetector {
+        &self.detector
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{DetectorCreateError, FreqDetector, OutputUnit};
+
+    use super::Tuner;
+
+    #[test]
+    fn finds_the_string_and_its_detuning() {
+        use std::f32::consts::TAU;
+        let sample_count = 4096;
+        let tuner = Tuner::new(
+            FreqDetector::new(44100, sample_count).unwrap(),
+            &Tuner::GUITAR_STANDARD,
+        )
+        .unwrap();
+        let pluck = |freq: f32| {
+            (0..sample_count)
+                .map(|i| {
+                    // louder octave than the fundamental, as with a real string
+                    [0.4, 1.0, 0.6, 0.3]
+                        .iter()
+                        .enumerate()
+                        .map(|(h, amplitude)| {
+                            let harmonic = freq * (h + 1) as f32;
+                            amplitude * (i as f32 / 44100.0 * harmonic * TAU).sin()
+                        })
+                        .sum()
+                })
+                .collect::<Vec<f32>>()
+        };
+
+        // A string 10 cents sharp
+        let reading = tuner
+            .tune(&pluck(110.0 * 2f32.powf(10.0 / 1200.0)))
+            .unwrap()
+            .unwrap();
+        assert_eq!((reading.string, reading.target), (1, 110.0));
+        assert!((reading.cents_off - 10.0).abs() < 1.0, "{reading:?}");
+
+        // low E string 30 cents flat
+        let reading = tuner
+            .tune(&pluck(82.41 * 2f32.powf(-30.0 / 1200.0)))
+            .unwrap()
+            .unwrap();
+        assert_eq!(reading.string, 0);
+        assert!((reading.cents_off + 30.0).abs() < 1.0, "{reading:?}");
+
+        assert_eq!(tuner.tune(&vec![0.0; sample_count]).unwrap(), None);
+
+        // readings are in Hz whatever the detector reports
+        let mut detector = FreqDetector::new(44100, sample_count).unwrap();
+        detector.set_output_unit(OutputUnit::BucketIndex);
+        let bucket_tuner = Tuner::new(detector, &Tuner::GUITAR_STANDARD).unwrap();
+        let reading = bucket_tuner.tune(&pluck(110.0)).unwrap().unwrap();
+        assert_eq!(reading.string, 1);
+        assert!((reading.freq - 110.0).abs() < 1.0, "{reading:?}");
+        assert!(reading.cents_off.abs() < 5.0, "{reading:?}");
+
+        for strings in [&[][..], &[0.0], &[82.41, 30000.0]] {
+            assert!(matches!(
+                Tuner::new(FreqDetector::new(44100, sample_count).unwrap(), strings),
+                Err(DetectorCreateError::InvalidTargetFreq)
+            ));
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use realfft::RealFftPlanner;
+use rustfft::{num_traits::Float, FftNum};
+
+use crate::{compare, float, WindowFunction};
+
+/// Periodic pitch modulation found by [vibrato]
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Vibrato<T = f32> {
+    /// How many times per second the pitch goes up and down
+    pub rate: T,
+    /// How far the pitch swings to either side of its average, in cents
+    pub depth_cents: T,
+}
+
+/// The spectrum of the pitch curve is sampled this many times more densely
+/// than its length allows, to pin down the rate
+const ZERO_PADDING: usize = 8;
+
+/// Strongest periodic modulation of a pitch curve taken `frame_rate` times per second,
+/// e.g. the frequencies detected by [crate::FreqDetector::detect_windows].
+///
+/// The curve is converted to cents and a linear trend is removed, so that a slow drift
+/// is not mistaken for vibrato, then the strongest component of its spectrum is reported.
+/// Rates below two cycles per curve are not considered, so the curve should cover
+/// a few periods of the vibrato, half a second or more for singing.
+///
+/// Returns `None` if there are fewer than 4 pitches, or any of them is not positive,
+/// e.g. the `0.0` reported for silence.
+pub fn vibrato<T: FftNum + Float>(pitches: &[T], frame_rate: T) -> Option<Vibrato<T>> {
+    if pitches.len() < 4 || !pitches.iter().all(|p| *p > T::zero() && p.is_finite()) {
+        return None;
+    }
+    let cents = pitches
+        .iter()
+        .map(|p| float::<T>(1200.0) * (*p / pitches[0]).log2())
+        .collect::<Vec<_>>();
+
+    // least-squares line through the curve
+    let len = float::<T>(cents.len() as f64);
+    let mean_time = (len - T::one()) / float(2.0);
+    let mean_cents = cents.iter().fold(T::zero(), |sum, c| sum + *c) / len;
+    let (covariance, variance) =
+        cents
+            .iter()
+            .enumerate()
+            .fold((T::zero(), T::zero()), |(covariance, variance), (i, c)| {
+                let time = float::<T>(i as f64) - mean_time;
+                (
+                    covariance + time * (*c - mean_cents),
+                    variance + time * time,
+                )
+            });
+    let slope = covariance / variance;
+
+    let window = WindowFunction::Hann;
+    let fft = RealFftPlanner::<T>::new().plan_fft_forward(cents.len() * ZERO_PADDING);
+    let mut input = fft.make_input_vec();
+    for (i, (c, w)) in cents
+        .iter()
+        .zip(window.coefficients(cents.len()))
+        .enumerate()
+    {
+        let trend = mean_cents + slope * (float::<T>(i as f64) - mean_time);
+        input[i] = (*c - trend) * float(w);
+    }
+    let mut output = fft.make_output_vec();
+    fft.process(&mut input, &mut output).ok()?;
+    let magnitudes = output.iter().map(|c| c.norm()).collect::<Vec<_>>();
+
+    // skips the main lobe around 0 Hz
+    let first = window.main_lobe_half_width(cents.len(), fft.len());
+    let peak = (first.max(1)..magnitudes.len() - 1)
+        .max_by(|&b1, &b2| compare(magnitudes[b1], magnitudes[b2]))?;
+    if magnitudes[peak] <= T::zero() {
+        return None;
+    }
+    // parabola through the log magnitudes, exact enough with this much padding
+    let [left, center, right] = [peak - 1, peak, peak + 1].map(|b| magnitudes[b].ln());
+    let curvature = left - float::<T>(2.0) * center + right;
+    let offset = if curvature < T::zero() {
+        float::<T>(0.5) * (left - right) / curvature
+    } else {
+        T::zero()
+    };
+    let peak_magnitude = (center - float::<T>(0.25) * (left - right) * offset).exp();
+
+    Some(Vibrato {
+        rate: (float::<T>(peak as f64) + offset) * frame_rate / float(fft.len() as f64),
+        depth_cents: float::<T>(2.0 * window.amplitude_correction()) * peak_magnitude / len,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::vibrato;
+
+    #[test]
+    fn vibrato_of_pitch_curve() {
+        use std::f32::consts::TAU;
+        let frame_rate = 100.0;
+        // two seconds of 5.5 Hz vibrato, 40 cents deep, drifting up by 30 cents
+        let pitches = (0..200)
+            .map(|i| {
+                let time = i as f32 / frame_rate;
+                let cents = 40.0 * (time * 5.5 * TAU).sin() + 15.0 * time;
+                440.0 * 2.0f32.powf(cents / 1200.0)
+            })
+            .collect::<Vec<_>>();
+
+        let found = vibrato(&pitches, frame_rate).unwrap();
+        assert!((found.rate - 5.5).abs() < 0.05, "{found:?}");
+        assert!((found.depth_cents - 40.0).abs() < 1.0, "{found:?}");
+
+        let mut with_silence = pitches.clone();
+        with_silence[50] = 0.0;
+        assert_eq!(vibrato(&with_silence, frame_rate), None);
+        assert_eq!(vibrato(&pitches[..3], frame_rate), None);
+    }
+}
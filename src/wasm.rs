@@ -0,0 +1,62 @@
+use wasm_bindgen::prelude::*;
+
+use crate::{DetectionResult, FreqDetector};
+
+/// JavaScript-facing wrapper around an `f32` [FreqDetector]
+///
+/// Errors are thrown as JS `Error`s. Blocks handed out by the Web Audio API
+/// are `Float32Array`s, which map to the `&[f32]` taken by [Self::detect].
+#[wasm_bindgen]
+pub struct WasmFreqDetector {
+    detector: FreqDetector<f32>,
+}
+
+/// Result of [WasmFreqDetector::detect_full], see [DetectionResult]
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WasmDetection {
+    /// Frequency of the peak, `0` for silence
+    pub frequency: f32,
+    pub magnitude: f32,
+    pub confidence: f32,
+    #[wasm_bindgen(js_name = isSilent)]
+    pub is_silent: bool,
+}
+
+impl From<DetectionResult> for WasmDetection {
+    fn from(result: DetectionResult) -> Self {
+        Self {
+            frequency: result.frequency,
+            magnitude: result.magnitude,
+            confidence: result.confidence,
+            is_silent: result.is_silent,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl WasmFreqDetector {
+    /// See [FreqDetector::new]
+    #[wasm_bindgen(constructor)]
+    pub fn new(sample_rate: usize, sample_count: usize) -> Result<WasmFreqDetector, JsError> {
+        Ok(Self {
+            detector: FreqDetector::new(sample_rate, sample_count)?,
+        })
+    }
+
+    /// See [FreqDetector::detect_mut]
+    pub fn detect(&mut self, samples: &[f32]) -> Result<f32, JsError> {
+        Ok(self.detector.detect_mut(samples)?)
+    }
+
+    /// See [FreqDetector::detect_full]
+    #[wasm_bindgen(js_name = detectFull)]
+    pub fn detect_full(&self, samples: &[f32]) -> Result<WasmDetection, JsError> {
+        Ok(self.detector.detect_full(samples)?.into())
+    }
+
+    #[wasm_bindgen(getter, js_name = sampleCount)]
+    pub fn sample_count(&self) -> usize {
+        self.detector.sample_count()
+    }
+}
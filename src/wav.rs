@@ -0,0 +1,352 @@
+use std::{io, path::Path, sync::Arc};
+
+use hound::{SampleFormat, WavReader};
+use thiserror::Error;
+
+use crate::{
+    DetectError, DetectorCreateError, FreqDetector, FreqDetectorConfig, Scratch, TimedDetection,
+};
+
+/// Samples of a WAV file, with all channels mixed down to mono
+///
+/// Integer PCM of 8 to 32 bits and 32-bit float samples are read with [hound],
+/// and scaled to between `-1.0` and `1.0`.
+///
+/// ```no_run
+/// use freq_det::{FreqDetector, WavSamples};
+///
+/// let wav = WavSamples::read("recording.wav").unwrap();
+/// let detector = FreqDetector::new(wav.sample_rate, 4096).unwrap();
+/// for detection in detector.detect_all(&wav.samples, 1024).unwrap() {
+///     println!("{:.2} s: {:.1} Hz", detection.time, detection.frequency);
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct WavSamples {
+    pub sample_rate: usize,
+    /// Number of channels in the file before mixing
+    pub channels: usize,
+    /// Average of the channels of every frame
+    pub samples: Vec<f32>,
+}
+
+impl WavSamples {
+    /// # Errors
+    /// - if the file cannot be read
+    /// - see [Self::parse]
+    pub fn read(path: impl AsRef<Path>) -> Result<Self, WavError> {
+        Self::from_reader(WavReader::open(path)?)
+    }
+
+    /// Same as [Self::read] for a file already in memory
+    ///
+    /// # Errors
+    /// - if `bytes` are not a WAV file or are cut short
+    /// - if the samples are compressed or of an unsupported bit depth
+    pub fn parse(bytes: &[u8]) -> Result<Self, WavError> {
+        Self::from_reader(WavReader::new(bytes)?)
+    }
+
+    fn from_reader<R: io::Read>(reader: WavReader<R>) -> Result<Self, WavError> {
+        let spec = reader.spec();
+        let interleaved = match spec.sample_format {
+            SampleFormat::Float => reader
+                .into_samples::<f32>()
+                .collect::<Result<Vec<_>, _>>()?,
+            SampleFormat::Int => {
+                let scale = (1u64 << (spec.bits_per_sample - 1)) as f32;
+                reader
+                    .into_samples::<i32>()
+                    .map(|sample| sample.map(|sample| sample as f32 / scale))
+                    .collect::<Result<Vec<_>, _>>()?
+            }
+        };
+        let channels = (spec.channels as usize).max(1);
+        let samples = interleaved
+            .chunks_exact(channels)
+            .map(|frame| frame.iter().sum::<f32>() / channels as f32)
+            .collect();
+        Ok(Self {
+            sample_rate: spec.sample_rate as usize,
+            channels,
+            samples,
+        })
+    }
+}
+
+impl FreqDetector {
+    /// Reads a WAV file and detects the frequency of windows of `sample_count` samples
+    /// taken every `hop_size` samples, see [Self::detect_all] and [WavSamples].
+    ///
+    /// The windows are detected lazily by the returned iterator, with the settings of
+    /// this detector at the sample rate of the file, so files of any rate can be analyzed.
+    ///
+    /// ```no_run
+    /// use freq_det::FreqDetector;
+    ///
+    /// let detector = FreqDetector::new(44100, 4096).unwrap();
+    /// for detection in detector.detect_wav_file("recording.wav", 1024).unwrap() {
+    ///     let detection = detection.unwrap();
+    ///     println!("{:.2} s: {:.1} Hz", detection.time, detection.frequency);
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// - if the file cannot be read, see [WavSamples::read]
+    /// - if the settings do not work at the sample rate of the file, such as
+    ///   a frequency range above its Nyquist frequency
+    ///
+    /// # Panics
+    ///
+    /// - if `hop_size` is `0`
+    pub fn detect_wav_file(
+        &self,
+        path: impl AsRef<Path>,
+        hop_size: usize,
+    ) -> Result<WavDetections, WavError> {
+        assert!(hop_size > 0, "hop size must be at least 1 sample");
+        let wav = WavSamples::read(path)?;
+        let detector = self.with_sample_rate(wav.sample_rate)?;
+        Ok(WavDetections {
+            scratch: Scratch::new(detector.fft.as_ref()),
+            detector,
+            samples: wav.samples,
+            hop_size,
+            next_window: 0,
+        })
+    }
+
+    /// Detector with the same settings and peak picker for another sample rate
+    fn with_sample_rate(&self, sample_rate: usize) -> Result<Self, DetectorCreateError> {
+        if sample_rate == self.sample_rate {
+            return Ok(self.clone());
+        }
+        let mut detector = Self::from_config(FreqDetectorConfig {
+            sample_rate,
+            ..self.config()
+        })?;
+        detector.peak_picker = Arc::clone(&self.peak_picker);
+        Ok(detector)
+    }
+}
+
+/// Detections of the windows of a WAV file, see [FreqDetector::detect_wav_file]
+///
+/// The same buffers are reused for all of the windows.
+pub struct WavDetections {
+    detector: FreqDetector,
+    samples: Vec<f32>,
+    hop_size: usize,
+    next_window: usize,
+    scratch: Scratch<f32>,
+}
+
+impl WavDetections {
+    /// The detector used for the windows, at the sample rate of the file
+    pub fn detector(&self) -> &FreqDetector {
+        &self.detector
+    }
+
+    /// Samples of the file, mixed down to mono
+    pub fn samples(&self) -> &[f32] {
+        &self.samples
+    }
+
+    fn remaining(&self) -> usize {
+        let sample_count = self.detector.sample_count;
+        let windows = match self.samples.len().checked_sub(sample_count) {
+            Some(last_start) => last_start / self.hop_size + 1,
+            None => 0,
+        };
+        windows.saturating_sub(self.next_window)
+    }
+}
+
+impl Iterator for WavDetections {
+    type Item = Result<TimedDetection, DetectError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let start = self.next_window * self.hop_size;
+        let window = self
+            .samples
+            .get(start..start + self.detector.sample_count)?;
+        self.next_window += 1;
+        Some(
+            self.detector
+                .fill_magnitudes(window.iter().copied(), &mut self.scratch)
+                .and_then(|()| {
+                    self.detector.timed_detection(
+                        start,
+                        &self.scratch.magnitudes,
+                        &mut self.scratch.median_buffer,
+                    )
+                }),
+        )
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining(), Some(self.remaining()))
+    }
+}
+
+impl ExactSizeIterator for WavDetections {}
+
+#[derive(Error, Debug)]
+pub enum WavError {
+    #[error("Could not read the WAV file: {0}")]
+    Read(#[from] hound::Error),
+    #[error(transparent)]
+    Create(#[from] DetectorCreateError),
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::FreqDetector;
+
+    use super::{WavError, WavSamples};
+
+    const FORMAT_PCM: u16 = 1;
+    const FORMAT_FLOAT: u16 = 3;
+    const FORMAT_EXTENSIBLE: u16 = 0xFFFE;
+    /// `KSDATAFORMAT_SUBTYPE_PCM` without the format tag in its first two bytes
+    const SUBTYPE_GUID: [u8; 14] = [
+        0x00, 0x00, 0x00, 0x00, 0x10, 0x00, 0x80, 0x00, 0x00, 0xAA, 0x00, 0x38, 0x9B, 0x71,
+    ];
+
+    /// A minimal WAV file, `data` holds interleaved samples already encoded
+    fn wav_bytes(tag: u16, channels: u16, sample_rate: u32, bits: u16, data: &[u8]) -> Vec<u8> {
+        let mut fmt = vec![];
+        fmt.extend(tag.to_le_bytes());
+        fmt.extend(channels.to_le_bytes());
+        fmt.extend(sample_rate.to_le_bytes());
+        let block_align = channels * bits.div_ceil(8);
+        fmt.extend((sample_rate * block_align as u32).to_le_bytes());
+        fmt.extend(block_align.to_le_bytes());
+        fmt.extend(bits.to_le_bytes());
+        if tag == FORMAT_EXTENSIBLE {
+            fmt.extend(22u16.to_le_bytes());
+            fmt.extend(bits.to_le_bytes());
+            fmt.extend(0u32.to_le_bytes());
+            fmt.extend(FORMAT_PCM.to_le_bytes());
+            fmt.extend(SUBTYPE_GUID);
+        }
+
+        let mut bytes = b"RIFF\0\0\0\0WAVE".to_vec();
+        // an unknown chunk, to be skipped
+        bytes.extend(b"LIST\x04\0\0\0abcd");
+        bytes.extend(b"fmt ");
+        bytes.extend((fmt.len() as u32).to_le_bytes());
+        bytes.extend(fmt);
+        bytes.extend(b"data");
+        bytes.extend((data.len() as u32).to_le_bytes());
+        bytes.extend(data);
+        let riff_size = (bytes.len() - 8) as u32;
+        bytes[4..8].copy_from_slice(&riff_size.to_le_bytes());
+        bytes
+    }
+
+    #[test]
+    fn decodes_and_mixes_down_sample_formats() {
+        // two stereo frames, the channels of the first cancel out
+        let pcm16 = [16384i16, -16384, 16384, 0]
+            .iter()
+            .flat_map(|s| s.to_le_bytes())
+            .collect::<Vec<_>>();
+        let wav = WavSamples::parse(&wav_bytes(FORMAT_PCM, 2, 8000, 16, &pcm16)).unwrap();
+        assert_eq!((wav.sample_rate, wav.channels), (8000, 2));
+        assert_eq!(wav.samples, [0.0, 0.25]);
+
+        let pcm8 = [0u8, 128, 192];
+        let wav = WavSamples::parse(&wav_bytes(FORMAT_PCM, 1, 8000, 8, &pcm8)).unwrap();
+        assert_eq!(wav.samples, [-1.0, 0.0, 0.5]);
+
+        // -0.5 and 0.5
+        let pcm24 = [0x00, 0x00, 0xC0, 0x00, 0x00, 0x40];
+        let wav = WavSamples::parse(&wav_bytes(FORMAT_EXTENSIBLE, 1, 8000, 24, &pcm24)).unwrap();
+        assert_eq!(wav.samples, [-0.5, 0.5]);
+
+        let float = [0.75f32, -0.125]
+            .iter()
+            .flat_map(|s| s.to_le_bytes())
+            .collect::<Vec<_>>();
+        let wav = WavSamples::parse(&wav_bytes(FORMAT_FLOAT, 1, 8000, 32, &float)).unwrap();
+        assert_eq!(wav.samples, [0.75, -0.125]);
+
+        // hound reads no 64-bit float
+        assert!(matches!(
+            WavSamples::parse(&wav_bytes(FORMAT_FLOAT, 1, 8000, 64, &[0; 8])),
+            Err(WavError::Read(hound::Error::FormatError(_)))
+        ));
+        assert!(matches!(
+            WavSamples::parse(b"RIFF\0\0\0\0AVI "),
+            Err(WavError::Read(_))
+        ));
+        // a data size of 0xFFFFFFFF, as left by streaming writers, is an error
+        // rather than an overflow
+        let mut streamed = wav_bytes(FORMAT_PCM, 1, 8000, 16, &pcm16);
+        let data_size = streamed.len() - pcm16.len() - 4;
+        streamed[data_size..data_size + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(matches!(
+            WavSamples::parse(&streamed),
+            Err(WavError::Read(_))
+        ));
+
+        let mut truncated = wav_bytes(FORMAT_PCM, 1, 8000, 16, &pcm16);
+        truncated.truncate(truncated.len() - 3);
+        assert!(matches!(
+            WavSamples::parse(&truncated),
+            Err(WavError::Read(_))
+        ));
+    }
+
+    #[test]
+    fn detects_frequencies_in_a_file() {
+        use std::f32::consts::TAU;
+        let data = (0..44100)
+            .flat_map(|i| {
+                let sample = (i as f32 / 44100.0 * 440.0 * TAU).sin();
+                [sample, sample]
+            })
+            .flat_map(|s| ((s * 32767.0) as i16).to_le_bytes())
+            .collect::<Vec<_>>();
+        let path = std::env::temp_dir().join(format!("freq-det-{}.wav", std::process::id()));
+        std::fs::write(&path, wav_bytes(FORMAT_PCM, 2, 44100, 16, &data)).unwrap();
+
+        let detector = FreqDetector::new(44100, 4096).unwrap();
+        let detections = detector.detect_wav_file(&path, 4096);
+        // a detector for another rate is rebuilt for the one of the file
+        let mut other_rate = FreqDetector::new(48000, 4096).unwrap();
+        other_rate.set_freq_range(100.0, 1000.0).unwrap();
+        let other_rate = other_rate.detect_wav_file(&path, 2048);
+        let mut above_nyquist = FreqDetector::new(96000, 4096).unwrap();
+        above_nyquist.set_freq_range(30000.0, 40000.0).unwrap();
+        let above_nyquist = above_nyquist.detect_wav_file(&path, 4096);
+        std::fs::remove_file(&path).unwrap();
+
+        let detections = detections.unwrap();
+        assert_eq!(detections.len(), 10);
+        for detection in detections {
+            let detection = detection.unwrap();
+            assert!((detection.frequency - 440.0).abs() < 1.0, "{detection:?}");
+        }
+
+        let mut other_rate = other_rate.unwrap();
+        assert_eq!(other_rate.detector().sample_rate(), 44100);
+        assert_eq!(
+            other_rate.detector().config().freq_range,
+            Some((100.0, 1000.0))
+        );
+        assert_eq!(other_rate.samples().len(), 44100);
+        assert_eq!(other_rate.len(), 20);
+        let second = other_rate.nth(1).unwrap().unwrap();
+        assert_eq!(second.sample, 2048);
+        assert!((second.frequency - 440.0).abs() < 1.0, "{second:?}");
+        assert_eq!(other_rate.len(), 18);
+
+        assert!(matches!(above_nyquist, Err(WavError::Create(_))));
+        assert!(matches!(
+            detector.detect_wav_file(&path, 4096),
+            Err(WavError::Read(hound::Error::IoError(_)))
+        ));
+    }
+}
@@ -0,0 +1,206 @@
+use std::f64::consts::TAU;
+
+use rustfft::{num_traits::Float, FftNum};
+
+/// Window function applied to the samples before the FFT
+///
+/// Anything other than [WindowFunction::Rectangular] tapers the ends of the
+/// sample slice, which reduces spectral leakage when the signal frequency does
+/// not land exactly on a bucket center.
+///
+/// Tapering also scales the peaks down, see [WindowFunction::amplitude_correction]
+/// for recovering the amplitude of a tone.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum WindowFunction {
+    /// Samples are passed to the FFT as is, amplitude correction is `1.0`
+    #[default]
+    Rectangular,
+    /// Amplitude correction is `2.0`
+    Hann,
+    /// Amplitude correction is `1.852`
+    Hamming,
+    /// Amplitude correction is `2.381`
+    Blackman,
+    /// 4-term Blackman-Harris, with side lobes below -92 dB.
+    /// Amplitude correction is `2.788`
+    BlackmanHarris,
+    /// Flat-top window, which keeps the peak magnitude within a fraction of a percent
+    /// however far the tone is from a bucket center, at the cost of a very wide main lobe.
+    /// The choice for measuring amplitudes. Amplitude correction is `4.639`
+    FlatTop,
+}
+
+impl WindowFunction {
+    /// Periodic window coefficients, as is customary for spectral analysis
+    pub(crate) fn coefficients(self, len: usize) -> Vec<f64> {
+        let cosine_sum = |a: &[f64]| {
+            (0..len)
+                .map(|n| {
+                    let phase = TAU * n as f64 / len as f64;
+                    a.iter()
+                        .enumerate()
+                        .map(|(k, a_k)| {
+                            let sign = if k % 2 == 0 { 1.0 } else { -1.0 };
+                            sign * a_k * (k as f64 * phase).cos()
+                        })
+                        .sum()
+                })
+                .collect()
+        };
+        cosine_sum(self.cosine_terms())
+    }
+
+    /// `a_k` of the window `sum((-1)^k * a_k * cos(k * phase))`, all of them are cosine sums
+    pub(crate) fn cosine_terms(self) -> &'static [f64] {
+        match self {
+            WindowFunction::Rectangular => &[1.0],
+            WindowFunction::Hann => &[0.5, 0.5],
+            WindowFunction::Hamming => &[0.54, 0.46],
+            WindowFunction::Blackman => &Self::BLACKMAN,
+            WindowFunction::BlackmanHarris => &Self::BLACKMAN_HARRIS,
+            WindowFunction::FlatTop => &Self::FLAT_TOP,
+        }
+    }
+
+    /// Factor to multiply the magnitude of a bucket by to undo the scaling
+    /// caused by the window, which is `1 / mean(window)`.
+    ///
+    /// The amplitude of a tone is `2 * magnitude * amplitude_correction / sample_count`,
+    /// where `magnitude` is the strongest bucket of its peak in [crate::FreqDetector::spectrum].
+    /// Off-center tones lose up to 36% of the magnitude with [WindowFunction::Rectangular]
+    /// and up to 15% with [WindowFunction::Hann], [WindowFunction::FlatTop] avoids that.
+    pub fn amplitude_correction(self) -> f64 {
+        1.0 / self.cosine_terms()[0]
+    }
+
+    const BLACKMAN: [f64; 3] = [0.42, 0.5, 0.08];
+    const BLACKMAN_HARRIS: [f64; 4] = [0.35875, 0.48829, 0.14128, 0.01168];
+    const FLAT_TOP: [f64; 5] = [
+        0.21557895,
+        0.41663158,
+        0.277263158,
+        0.083578947,
+        0.006947368,
+    ];
+
+    /// Half-width of the main lobe of the window's spectrum, in buckets
+    /// of an FFT of `fft_len` taken over `len` samples
+    pub(crate) fn main_lobe_half_width(self, len: usize, fft_len: usize) -> usize {
+        let unpadded = match self {
+            WindowFunction::Rectangular => 1,
+            WindowFunction::Hann | WindowFunction::Hamming => 2,
+            WindowFunction::Blackman => 3,
+            WindowFunction::BlackmanHarris => 4,
+            WindowFunction::FlatTop => 5,
+        };
+        (unpadded * fft_len).div_ceil(len)
+    }
+}
+
+/// Maps the relative weight of the right bucket in the peak window
+/// to the offset of the true peak from the left bucket.
+///
+/// For the rectangular window the two are the same, which is why a plain
+/// weighted average of the two buckets works. Other windows widen the main lobe,
+/// so the relation is tabulated from the window's own frequency response.
+/// Zero-padding to `fft_len` makes the buckets narrower than the main lobe, which is
+/// accounted for as well.
+#[derive(Clone)]
+pub(crate) struct PeakOffsetTable<T> {
+    weights: Vec<T>,
+}
+
+impl<T: FftNum + Float> PeakOffsetTable<T> {
+    const STEPS: usize = 32;
+
+    pub(crate) fn new(window: &[f64], fft_len: usize) -> Self {
+        let response = |offset: f64| {
+            let (re, im) = window
+                .iter()
+                .enumerate()
+                .fold((0.0, 0.0), |(re, im), (n, w)| {
+                    let phase = TAU * offset * n as f64 / fft_len as f64;
+                    (re + w * phase.cos(), im - w * phase.sin())
+                });
+            (re * re + im * im).sqrt()
+        };
+        let weights = (0..=Self::STEPS)
+            .map(|step| {
+                let offset = step as f64 / Self::STEPS as f64;
+                let left = response(offset);
+                let right = response(1.0 - offset);
+                T::from_f64(right / (left + right)).expect("weight to fit into T")
+            })
+            .collect();
+        Self { weights }
+    }
+
+    /// `weight` is `right / (left + right)` for the magnitudes of the two buckets
+    pub(crate) fn offset(&self, weight: T) -> T {
+        let step = self
+            .weights
+            .partition_point(|w| *w < weight)
+            .clamp(1, Self::STEPS);
+        let (lo, hi) = (self.weights[step - 1], self.weights[step]);
+        let within_step = if hi > lo {
+            ((weight - lo) / (hi - lo)).max(T::zero()).min(T::one())
+        } else {
+            T::from_f64(0.5).expect("0.5 to fit into T")
+        };
+        let step = T::from_usize(step - 1).expect("step to fit into T");
+        let steps = T::from_usize(Self::STEPS).expect("step count to fit into T");
+        (step + within_step) / steps
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{FreqDetector, WindowFunction};
+
+    #[test]
+    fn flat_top_measures_amplitude() {
+        use std::f32::consts::TAU;
+        let sample_count = 4096;
+        let amplitude = 0.7;
+        // half way between two buckets, the worst case for scalloping
+        let freq = 100.5 * 44100.0 / sample_count as f32;
+        let samples = (0..sample_count)
+            .map(|i| amplitude * (i as f32 / 44100.0 * freq * TAU).sin())
+            .collect::<Vec<f32>>();
+        let measured = |window: WindowFunction| {
+            let freq_detector = FreqDetector::with_window(44100, sample_count, window).unwrap();
+            let peak = freq_detector
+                .spectrum(&samples)
+                .unwrap()
+                .into_iter()
+                .map(|(_, magnitude)| magnitude)
+                .fold(0.0, f32::max);
+            2.0 * peak * window.amplitude_correction() as f32 / sample_count as f32
+        };
+
+        let flat_top = measured(WindowFunction::FlatTop);
+        assert!(
+            (flat_top - amplitude).abs() < amplitude * 0.002,
+            "{flat_top}"
+        );
+        let hann = measured(WindowFunction::Hann);
+        assert!((hann - amplitude).abs() > amplitude * 0.1, "{hann}");
+    }
+
+    #[test]
+    fn amplitude_correction_is_inverse_mean() {
+        for window in [
+            WindowFunction::Rectangular,
+            WindowFunction::Hann,
+            WindowFunction::Hamming,
+            WindowFunction::Blackman,
+            WindowFunction::BlackmanHarris,
+            WindowFunction::FlatTop,
+        ] {
+            let coefficients = window.coefficients(1024);
+            let mean = coefficients.iter().sum::<f64>() / coefficients.len() as f64;
+            assert!((window.amplitude_correction() * mean - 1.0).abs() < 1e-9);
+        }
+    }
+}
@@ -0,0 +1,189 @@
+use rustfft::{num_traits::Float, FftNum};
+
+use crate::{check_finite, float, DetectError, DetectorCreateError};
+
+/// Time-domain pitch detector using the YIN algorithm
+///
+/// Better suited than the spectral [crate::FreqDetector] for monophonic sources,
+/// such as voice, at small sample counts. The lowest detectable frequency is
+/// `2 * sample_rate / sample_count`, as the period has to fit into half of the samples.
+///
+/// Detection takes time proportional to `sample_count²`.
+pub struct YinDetector<T = f32> {
+    sample_rate: usize,
+    sample_count: usize,
+    threshold: T,
+}
+
+impl<T: FftNum + Float> YinDetector<T> {
+    /// `threshold` is the cutoff of the normalized difference function,
+    /// `0.1`-`0.15` work well. Lower values reject more noisy signals.
+    ///
+    /// # Errors
+    /// - if sample rate is 0
+    /// - if fewer than 4 samples are passed
+    /// - if `threshold` is not between `0.0` and `1.0`
+    pub fn new(
+        sample_rate: usize,
+        sample_count: usize,
+        threshold: T,
+    ) -> Result<Self, DetectorCreateError> {
+        if sample_rate < 1 {
+            return Err(DetectorCreateError::SampleRateTooLow);
+        }
+        if sample_count < 4 {
+            return Err(DetectorCreateError::TooFewSamples);
+        }
+        if !(threshold > T::zero() && threshold < T::one()) {
+            return Err(DetectorCreateError::InvalidThreshold);
+        }
+        Ok(Self {
+            sample_rate,
+            sample_count,
+            threshold,
+        })
+    }
+
+    /// Returns `None` if no pitch was found, e.g. for noise or silence
+    ///
+    /// # Errors
+    ///
+    /// - if `samples.len()` does not match the `sample_count` passed to [Self::new]
+    /// - if there are `NaN`s or infinities in the sample slice
+    pub fn detect(&self, samples: &[T]) -> Result<Option<T>, DetectError> {
+        Ok(self
+            .detect_with_aperiodicity(samples)?
+            .map(|(freq, _)| freq))
+    }
+
+    /// Same as [Self::detect], but also returns the aperiodicity of the signal
+    /// as `(frequency, aperiodicity)`.
+    ///
+    /// Aperiodicity is the normalized difference at the detected period, `0.0` for
+    /// a perfectly periodic signal and up to the threshold passed to [Self::new],
+    /// as anything above it is not reported as a pitch.
+    ///
+    /// # Errors
+    ///
+    /// Same as [Self::detect]
+    pub fn detect_with_aperiodicity(&self, samples: &[T]) -> Result<Option<(T, T)>, DetectError> {
+        if samples.len() != self.sample_count {
+            return Err(DetectError::SampleCountMismatch {
+                expected: self.sample_count,
+                passed: samples.len(),
+            });
+        }
+        check_finite(samples)?;
+        let normalized = self.normalized_difference(samples);
+
+        let Some(mut period) = (1..normalized.len()).find(|&tau| normalized[tau] < self.threshold)
+        else {
+            return Ok(None);
+        };
+        // walk down to the bottom of the dip
+        while period + 1 < normalized.len() && normalized[period + 1] < normalized[period] {
+            period += 1;
+        }
+
+        let aperiodicity = normalized[period];
+        let period = refine_minimum(&normalized, period);
+        Ok(Some((
+            float::<T>(self.sample_rate as f64) / period,
+            aperiodicity,
+        )))
+    }
+
+    /// Cumulative mean normalized difference for every lag up to half of the samples
+    fn normalized_difference(&self, samples: &[T]) -> Vec<T> {
+        let window = self.sample_count / 2;
+        let mut normalized = vec![T::one(); window];
+        let mut running_sum = T::zero();
+        for tau in 1..window {
+            let difference = (0..window).fold(T::zero(), |sum, j| {
+                let delta = samples[j] - samples[j + tau];
+                sum + delta * delta
+            });
+            running_sum = running_sum + difference;
+            normalized[tau] = if running_sum > T::zero() {
+                difference * float(tau as f64) / running_sum
+            } else {
+                T::one()
+            };
+        }
+        normalized
+    }
+}
+
+/// Position of the minimum of a parabola through `tau` and its neighbors
+fn refine_minimum<T: FftNum + Float>(values: &[T], tau: usize) -> T {
+    let tau_float = float::<T>(tau as f64);
+    if tau < 1 || tau + 1 >= values.len() {
+        return tau_float;
+    }
+    let (a, b, c) = (values[tau - 1], values[tau], values[tau + 1]);
+    let curvature = a - float::<T>(2.0) * b + c;
+    if curvature <= T::zero() {
+        return tau_float;
+    }
+    tau_float + float::<T>(0.5) * (a - c) / curvature
+}
+
+#[cfg(test)]
+mod tests {
+    use super::YinDetector;
+
+    #[test]
+    fn yin_detects_pitch_and_rejects_noise() {
+        use std::f32::consts::TAU;
+        let sample_count = 2048;
+        let detector = YinDetector::new(44100, sample_count, 0.1).unwrap();
+
+        for freq in [82.41, 440.0, 1234.5] {
+            let samples = (0..sample_count)
+                .map(|i| (i as f32 / 44100.0 * freq * TAU).sin())
+                .collect::<Vec<f32>>();
+            let detected = detector.detect(&samples).unwrap().unwrap();
+            assert!(
+                (detected - freq).abs() < 0.5,
+                "detected {detected} expected {freq}"
+            );
+        }
+
+        let mut state = 12345u32;
+        let noise = (0..sample_count)
+            .map(|_| {
+                state = state.wrapping_mul(1664525).wrapping_add(1013904223);
+                state as f32 / u32::MAX as f32 - 0.5
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(detector.detect(&noise).unwrap(), None);
+        assert_eq!(detector.detect(&vec![0.0; sample_count]).unwrap(), None);
+    }
+
+    #[test]
+    fn aperiodicity_grows_with_noise() {
+        use std::f32::consts::TAU;
+        let sample_count = 2048;
+        let detector = YinDetector::new(44100, sample_count, 0.15).unwrap();
+        let mut state = 12345u32;
+        let mut aperiodicity = |noise_level: f32| {
+            let samples = (0..sample_count)
+                .map(|i| {
+                    state = state.wrapping_mul(1664525).wrapping_add(1013904223);
+                    let noise = state as f32 / u32::MAX as f32 - 0.5;
+                    (i as f32 / 44100.0 * 440.0 * TAU).sin() + noise_level * noise
+                })
+                .collect::<Vec<f32>>();
+            let (freq, aperiodicity) = detector
+                .detect_with_aperiodicity(&samples)
+                .unwrap()
+                .unwrap();
+            assert!((freq - 440.0).abs() < 1.0, "{freq}");
+            aperiodicity
+        };
+        let clean = aperiodicity(0.0);
+        let noisy = aperiodicity(0.3);
+        assert!(clean < 0.001, "{clean}");
+        assert!(noisy > 10.0 * clean && noisy < 0.15, "{noisy}");
+    }
+}
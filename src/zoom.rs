@@ -0,0 +1,228 @@
+use std::{f64::consts::TAU, sync::Arc};
+
+use rustfft::{num_complex::Complex, num_traits::Float, Fft, FftNum, FftPlanner};
+
+use crate::{
+    check_finite, compare, float, DetectError, DetectorCreateError, Resampler, WindowFunction,
+};
+
+/// The decimated band is zero-padded to this many times its length for finer buckets
+const ZERO_PADDING: usize = 4;
+
+/// Detects the strongest frequency within a narrow band with a zoom FFT
+///
+/// The samples are shifted down by `center_hz` so the band lies around 0 Hz, low-pass
+/// filtered and decimated to twice `bandwidth_hz` with a [Resampler], and only the few
+/// remaining samples are transformed, after a Hann window. Finding a tone to within
+/// parts of a hertz still takes a long recording, two tones can only be told apart
+/// when they are more than about `2 * sample_rate / sample_count` apart, but no FFT of
+/// the whole recording is computed. The zoomed buckets are that width over `4`
+/// and the peak is refined between them.
+///
+/// ```
+/// use freq_det::ZoomDetector;
+///
+/// // 10 s of vibration around 120 Hz, in buckets of 0.02 Hz
+/// let detector = ZoomDetector::new(8000, 80000, 120.0, 10.0).unwrap();
+/// # let samples = vec![0.0; 80000];
+/// if let Some(freq) = detector.detect(&samples).unwrap() {
+///     println!("{freq:.3} Hz");
+/// }
+/// ```
+pub struct ZoomDetector<T: FftNum = f32> {
+    sample_rate: usize,
+    sample_count: usize,
+    center: T,
+    bandwidth: T,
+    decimated_rate: usize,
+    fft: Arc<dyn Fft<T>>,
+}
+
+impl<T: FftNum + Float> ZoomDetector<T> {
+    /// Searches from `center_hz - bandwidth_hz / 2` to `center_hz + bandwidth_hz / 2`.
+    ///
+    /// # Errors
+    /// - if sample rate is 0
+    /// - if `bandwidth_hz` is not positive
+    /// - if the band is not between 0 Hz and the Nyquist frequency
+    /// - if fewer than 4 samples are left after decimating
+    pub fn new(
+        sample_rate: usize,
+        sample_count: usize,
+        center_hz: T,
+        bandwidth_hz: T,
+    ) -> Result<Self, DetectorCreateError> {
+        if sample_rate < 1 {
+            return Err(DetectorCreateError::SampleRateTooLow);
+        }
+        if bandwidth_hz.is_nan() || bandwidth_hz <= T::zero() {
+            return Err(DetectorCreateError::EmptyFreqRange);
+        }
+        let half_band = bandwidth_hz / float(2.0);
+        if center_hz - half_band < T::zero()
+            || center_hz + half_band > float(sample_rate as f64 / 2.0)
+        {
+            return Err(DetectorCreateError::InvalidTargetFreq);
+        }
+        let decimated_rate = (bandwidth_hz * float(2.0))
+            .ceil()
+            .to_usize()
+            .unwrap_or(sample_rate)
+            .min(sample_rate);
+        let decimated_len = sample_count * decimated_rate / sample_rate;
+        if decimated_len < 4 {
+            return Err(DetectorCreateError::TooFewSamples);
+        }
+        let fft =
+            FftPlanner::new().plan_fft_forward(decimated_len.next_power_of_two() * ZERO_PADDING);
+        Ok(Self {
+            sample_rate,
+            sample_count,
+            center: center_hz,
+            bandwidth: bandwidth_hz,
+            decimated_rate,
+            fft,
+        })
+    }
+
+    /// Strongest frequency within the band, `None` if the band is silent
+    ///
+    /// # Errors
+    ///
+    /// - if `samples.len()` does not match the `sample_count` passed to [Self::new]
+    /// - if there are `NaN`s or infinities in the sample slice
+    pub fn detect(&self, samples: &[T]) -> Result<Option<T>, DetectError> {
+        let magnitudes = self.magnitudes(samples)?;
+        let fft_len = self.fft.len();
+        let bucket_width = self.bucket_width();
+        let half_band = (self.bandwidth / float(2.0) / bucket_width)
+            .floor()
+            .to_isize()
+            .unwrap_or(0);
+        // negative offsets from the center wrap around to the end of the spectrum
+        let magnitude_at = |offset: isize| magnitudes[offset.rem_euclid(fft_len as isize) as usize];
+
+        let Some(peak) =
+            (-half_band..=half_band).max_by(|a, b| compare(magnitude_at(*a), magnitude_at(*b)))
+        else {
+            return Ok(None);
+        };
+        if magnitude_at(peak) <= T::zero() {
+            return Ok(None);
+        }
+        let refinement = if peak.abs() < half_band {
+            // parabola through the logarithms, which fits the main lobe of the Hann window
+            let ln = |offset| magnitude_at(offset).max(T::min_positive_value()).ln();
+            let (a, b, c) = (ln(peak - 1), ln(peak), ln(peak + 1));
+            let curvature = a - float::<T>(2.0) * b + c;
+            if curvature < T::zero() {
+                float::<T>(0.5) * (a - c) / curvature
+            } else {
+                T::zero()
+            }
+        } else {
+            T::zero()
+        };
+        let offset = float::<T>(peak as f64) + refinement;
+        Ok(Some(self.center + offset * bucket_width))
+    }
+
+    /// Magnitudes of the zoomed spectrum, the bucket at `i` is `i * bucket_width` Hz
+    /// above the center, wrapping around to below it in the second half
+    fn magnitudes(&self, samples: &[T]) -> Result<Vec<T>, DetectError> {
+        if samples.len() != self.sample_count {
+            return Err(DetectError::SampleCountMismatch {
+                expected: self.sample_count,
+                passed: samples.len(),
+            });
+        }
+        check_finite(samples)?;
+
+        // in f64, as the phase grows large over long recordings
+        let omega = -TAU * self.center.to_f64().unwrap_or(0.0) / self.sample_rate as f64;
+        let (real, imaginary): (Vec<T>, Vec<T>) = samples
+            .iter()
+            .enumerate()
+            .map(|(n, s)| {
+                let (sin, cos) = (omega * n as f64).sin_cos();
+                (*s * float(cos), *s * float(sin))
+            })
+            .unzip();
+        let decimate = |input: &[T]| {
+            let mut resampler = Resampler::new(self.sample_rate, self.decimated_rate)
+                .expect("rates to be checked in new");
+            let mut output = vec![];
+            resampler.process(input, &mut output);
+            output
+        };
+        let (real, imaginary) = (decimate(&real), decimate(&imaginary));
+
+        let window = WindowFunction::Hann.coefficients(real.len());
+        let mut buffer = vec![Complex::new(T::zero(), T::zero()); self.fft.len()];
+        for (i, w) in window.iter().enumerate() {
+            buffer[i] = Complex::new(real[i], imaginary[i]) * float::<T>(*w);
+        }
+        self.fft.process(&mut buffer);
+        let magnitudes = buffer.iter().map(|c| c.norm()).collect::<Vec<_>>();
+        if magnitudes.iter().any(|m| m.is_nan()) {
+            return Err(DetectError::NansFound);
+        }
+        Ok(magnitudes)
+    }
+
+    /// Spacing of the zoomed buckets in Hz
+    pub fn bucket_width(&self) -> T {
+        float::<T>(self.decimated_rate as f64) / float(self.fft.len() as f64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{DetectError, DetectorCreateError};
+
+    use super::ZoomDetector;
+
+    #[test]
+    fn zooms_into_a_narrow_band() {
+        use std::f64::consts::TAU;
+        let sample_count = 80000;
+        let detector = ZoomDetector::new(8000, sample_count, 120.0, 10.0).unwrap();
+        assert!(
+            detector.bucket_width() < 0.03,
+            "{}",
+            detector.bucket_width()
+        );
+
+        for freq in [117.3, 120.0, 120.04, 123.9] {
+            // a louder tone outside of the band, and a slight hum in it
+            let samples = (0..sample_count)
+                .map(|i| {
+                    let t = i as f64 / 8000.0;
+                    let sample = 0.3 * (t * freq * TAU).sin()
+                        + 0.6 * (t * 150.0 * TAU).sin()
+                        + 0.01 * (t * 118.0 * TAU).sin();
+                    sample as f32
+                })
+                .collect::<Vec<f32>>();
+            let detected = detector.detect(&samples).unwrap().unwrap();
+            assert!(
+                (detected - freq as f32).abs() < 0.01,
+                "{detected} for {freq}"
+            );
+        }
+
+        assert_eq!(detector.detect(&vec![0.0; sample_count]).unwrap(), None);
+        assert!(matches!(
+            detector.detect(&[0.0; 100]),
+            Err(DetectError::SampleCountMismatch { .. })
+        ));
+        assert!(matches!(
+            ZoomDetector::<f32>::new(8000, sample_count, 3999.0, 10.0),
+            Err(DetectorCreateError::InvalidTargetFreq)
+        ));
+        assert!(matches!(
+            ZoomDetector::<f32>::new(8000, 1000, 120.0, 10.0),
+            Err(DetectorCreateError::TooFewSamples)
+        ));
+    }
+}
@@ -0,0 +1,57 @@
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+
+/// Runs `freq-det analyze` with `args`, feeding `stdin`
+fn analyze(args: &[&str], stdin: &[u8]) -> String {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_freq-det"))
+        .arg("analyze")
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(stdin).unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn analyzes_raw_pcm_from_stdin() {
+    use std::f32::consts::TAU;
+    // half a second of A4, then half a second of silence
+    let pcm = (0..8000)
+        .map(|i| {
+            if i < 4000 {
+                (i as f32 / 8000.0 * 440.0 * TAU).sin()
+            } else {
+                0.0
+            }
+        })
+        .flat_map(|s| ((s * 20000.0) as i16).to_le_bytes())
+        .collect::<Vec<u8>>();
+    let args = ["--rate", "8000", "--sample-count", "1024", "--hop", "1000"];
+
+    let csv = analyze(&[&args[..], &["--format", "csv"]].concat(), &pcm);
+    let lines = csv.lines().collect::<Vec<_>>();
+    assert_eq!(lines[0], "time,frequency,note,cents_off,confidence");
+    assert_eq!(lines.len(), 1 + 7);
+    let first = lines[1].split(',').collect::<Vec<_>>();
+    assert_eq!(first[0], "0.0000");
+    assert!(
+        (first[1].parse::<f32>().unwrap() - 440.0).abs() < 2.0,
+        "{csv}"
+    );
+    assert_eq!(first[2], "A4");
+    assert_eq!(lines[7], "0.7500,0.00,,,0.000");
+
+    let json = analyze(&[&args[..], &["--format", "json"]].concat(), &pcm);
+    assert!(json.starts_with("[\n") && json.ends_with("]\n"), "{json}");
+    assert!(json.contains("\"note\": \"A4\""));
+    assert!(json.contains("\"note\": null"));
+
+    let text = analyze(&args, &pcm);
+    assert!(text.lines().last().unwrap().ends_with("silence"), "{text}");
+}
@@ -0,0 +1,26 @@
+use std::{env, fs, path::Path};
+
+/// `include/freq_det.h` is what cbindgen generates from `cbindgen.toml`,
+/// run with `FREQ_DET_BLESS=1` to regenerate it
+#[test]
+fn header_is_up_to_date() {
+    let crate_dir = env!("CARGO_MANIFEST_DIR");
+    let header_path = Path::new(crate_dir).join("include/freq_det.h");
+    let config = cbindgen::Config::from_file(Path::new(crate_dir).join("cbindgen.toml")).unwrap();
+    let mut generated = vec![];
+    cbindgen::Builder::new()
+        .with_config(config)
+        .with_src(Path::new(crate_dir).join("src/ffi.rs"))
+        .generate()
+        .unwrap()
+        .write(&mut generated);
+    let generated = String::from_utf8(generated).unwrap();
+    if env::var_os("FREQ_DET_BLESS").is_some() {
+        fs::write(&header_path, &generated).unwrap();
+    }
+    assert_eq!(
+        fs::read_to_string(&header_path).unwrap(),
+        generated,
+        "include/freq_det.h is outdated, run the test with FREQ_DET_BLESS=1"
+    );
+}